@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Where the user is sent after following an emailed action link.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionCodeSettings {
+    pub continue_url: String,
+    pub can_handle_code_in_app: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActionCodeMode {
+    SignIn,
+    ResetPassword,
+    VerifyEmail,
+    RecoverEmail,
+    VerifyAndChangeEmail,
+    RevertSecondFactorAddition,
+    Unknown(String),
+}
+
+impl ActionCodeMode {
+    fn parse(mode: &str) -> ActionCodeMode {
+        match mode {
+            "signIn" => ActionCodeMode::SignIn,
+            "resetPassword" => ActionCodeMode::ResetPassword,
+            "verifyEmail" => ActionCodeMode::VerifyEmail,
+            "recoverEmail" => ActionCodeMode::RecoverEmail,
+            "verifyAndChangeEmail" => ActionCodeMode::VerifyAndChangeEmail,
+            "revertSecondFactorAddition" => ActionCodeMode::RevertSecondFactorAddition,
+            _ => ActionCodeMode::Unknown(mode.to_owned()),
+        }
+    }
+}
+
+/// The result of `Firebase::apply_action_code`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedActionCode {
+    pub email: Option<String>,
+    /// Set when a `VERIFY_AND_CHANGE_EMAIL` code changed the user's email.
+    pub new_email: Option<String>,
+    request_type: Option<String>,
+}
+
+impl AppliedActionCode {
+    pub fn mode(&self) -> ActionCodeMode {
+        match self.request_type.as_deref() {
+            Some("VERIFY_EMAIL") => ActionCodeMode::VerifyEmail,
+            Some("RECOVER_EMAIL") => ActionCodeMode::RecoverEmail,
+            Some("VERIFY_AND_CHANGE_EMAIL") => ActionCodeMode::VerifyAndChangeEmail,
+            Some("REVERT_SECOND_FACTOR_ADDITION") => ActionCodeMode::RevertSecondFactorAddition,
+            request_type => ActionCodeMode::Unknown(request_type.unwrap_or_default().to_owned()),
+        }
+    }
+}
+
+/// The parameters of an action link sent by Firebase, e.g. an email sign-in link.
+#[derive(Debug, Clone)]
+pub struct ActionCodeLink {
+    pub mode: ActionCodeMode,
+    pub oob_code: String,
+    pub continue_url: Option<String>,
+    pub api_key: Option<String>,
+}
+
+impl ActionCodeLink {
+    /// Parses an action link, also when it is wrapped in a Dynamic Link (`link=` parameter).
+    /// Returns `None` when the link has no `mode` or `oobCode`.
+    pub fn parse(link: &str) -> Option<ActionCodeLink> {
+        let url = Url::parse(link).ok()?;
+        let query_param = |key: &str| {
+            url.query_pairs()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.into_owned())
+        };
+
+        match (query_param("mode"), query_param("oobCode")) {
+            (Some(mode), Some(oob_code)) => Some(ActionCodeLink {
+                mode: ActionCodeMode::parse(&mode),
+                oob_code,
+                continue_url: query_param("continueUrl"),
+                api_key: query_param("apiKey"),
+            }),
+            _ => ActionCodeLink::parse(&query_param("link")?),
+        }
+    }
+
+    pub fn is_sign_in(&self) -> bool {
+        self.mode == ActionCodeMode::SignIn
+    }
+}
@@ -0,0 +1,42 @@
+use std::fmt;
+
+use actix_web::http::StatusCode;
+
+use crate::verify::VerifyError;
+
+#[derive(Debug)]
+pub enum AuthRejection {
+    MissingFirebase,
+    MissingToken,
+    InvalidToken(VerifyError),
+    EmailNotVerified,
+}
+
+impl AuthRejection {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AuthRejection::MissingFirebase => StatusCode::INTERNAL_SERVER_ERROR,
+            AuthRejection::MissingToken => StatusCode::UNAUTHORIZED,
+            // The token can't be checked right now, which is no reason to discard it.
+            AuthRejection::InvalidToken(VerifyError::KeySource(_)) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            AuthRejection::InvalidToken(
+                VerifyError::MissingProjectId | VerifyError::UserLookup(_),
+            ) => StatusCode::INTERNAL_SERVER_ERROR,
+            AuthRejection::InvalidToken(_) => StatusCode::UNAUTHORIZED,
+            AuthRejection::EmailNotVerified => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+impl fmt::Display for AuthRejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuthRejection::MissingFirebase => write!(f, "Firebase is not registered as app data"),
+            AuthRejection::MissingToken => write!(f, "Missing bearer token"),
+            AuthRejection::InvalidToken(err) => write!(f, "Invalid token: {}", err),
+            AuthRejection::EmailNotVerified => write!(f, "Email not verified"),
+        }
+    }
+}
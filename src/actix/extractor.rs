@@ -0,0 +1,37 @@
+use actix_web::{dev::Payload, FromRequest, HttpRequest};
+use futures_util::future::LocalBoxFuture;
+
+use crate::verify::IdTokenClaims;
+
+use super::{auth_config, authenticate};
+
+/// A request whose `Authorization: Bearer` header carries a valid Firebase ID token.
+///
+/// Requires `web::Data<Firebase>` in the app data. When the route is wrapped by
+/// `FirebaseAuth` the user verified by the middleware is reused.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub id_token: String,
+    pub claims: IdTokenClaims,
+}
+
+impl AuthenticatedUser {
+    pub fn uid(&self) -> &str {
+        self.claims.uid()
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move {
+            let config = auth_config(&req);
+            authenticate(&req, &config)
+                .await
+                .map_err(|rejection| config.error(rejection))
+        })
+    }
+}
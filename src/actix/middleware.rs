@@ -0,0 +1,90 @@
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use futures_util::future::LocalBoxFuture;
+
+use super::{auth_config, authenticate, AuthConfig};
+
+/// Middleware rejecting every request without a valid Firebase ID token.
+///
+/// Verified users are stored in the request extensions, so handlers can take an
+/// `AuthenticatedUser` without verifying the token twice.
+#[derive(Default, Clone)]
+pub struct FirebaseAuth {
+    config: Option<AuthConfig>,
+}
+
+impl FirebaseAuth {
+    /// Uses `config` instead of the `AuthConfig` registered as app data.
+    pub fn with_config(config: AuthConfig) -> FirebaseAuth {
+        FirebaseAuth {
+            config: Some(config),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for FirebaseAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = FirebaseAuthMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(FirebaseAuthMiddleware {
+            service: Rc::new(service),
+            config: self.config.clone(),
+        }))
+    }
+}
+
+pub struct FirebaseAuthMiddleware<S> {
+    service: Rc<S>,
+    config: Option<AuthConfig>,
+}
+
+impl<S, B> Service<ServiceRequest> for FirebaseAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let config = self.config.clone();
+
+        Box::pin(async move {
+            let result = {
+                let http_request = req.request().clone();
+                let config = config.unwrap_or_else(|| auth_config(&http_request));
+                authenticate(&http_request, &config)
+                    .await
+                    .map_err(|rejection| config.error(rejection))
+            };
+
+            match result {
+                Ok(_) => service
+                    .call(req)
+                    .await
+                    .map(ServiceResponse::map_into_left_body),
+                Err(err) => Ok(req.error_response(err).map_into_right_body()),
+            }
+        })
+    }
+}
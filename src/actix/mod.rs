@@ -0,0 +1,150 @@
+pub mod error;
+pub mod extractor;
+pub mod middleware;
+
+pub use error::AuthRejection;
+pub use extractor::AuthenticatedUser;
+pub use middleware::FirebaseAuth;
+
+use std::rc::Rc;
+
+use actix_web::{
+    error::InternalError,
+    http::header::{self, HeaderValue},
+    web, HttpMessage, HttpRequest, HttpResponse,
+};
+use serde_json::json;
+
+use crate::{verify::VerifyOptions, Firebase};
+
+type ErrorHandler = Rc<dyn Fn(&AuthRejection) -> HttpResponse>;
+
+/// Configures how requests are authenticated by `AuthenticatedUser` and `FirebaseAuth`.
+///
+/// Register it with `App::app_data`; requests fall back to `AuthConfig::default()` otherwise.
+#[derive(Clone)]
+pub struct AuthConfig {
+    require_email_verified: bool,
+    check_revoked: bool,
+    error_handler: ErrorHandler,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            require_email_verified: false,
+            check_revoked: false,
+            error_handler: Rc::new(default_error_response),
+        }
+    }
+}
+
+impl AuthConfig {
+    /// Rejects users whose email is not verified with `403 Forbidden`.
+    pub fn require_email_verified(mut self, require: bool) -> AuthConfig {
+        self.require_email_verified = require;
+        self
+    }
+
+    /// Rejects tokens of deleted or disabled users and tokens revoked with
+    /// `Firebase::revoke_refresh_tokens`, at the cost of a user lookup per request.
+    pub fn check_revoked(mut self, check: bool) -> AuthConfig {
+        self.check_revoked = check;
+        self
+    }
+
+    /// Replaces the response sent when a request is rejected.
+    pub fn error_handler<F>(mut self, handler: F) -> AuthConfig
+    where
+        F: Fn(&AuthRejection) -> HttpResponse + 'static,
+    {
+        self.error_handler = Rc::new(handler);
+        self
+    }
+
+    pub(crate) fn error(&self, rejection: AuthRejection) -> actix_web::Error {
+        let mut response = (self.error_handler)(&rejection);
+        if rejection.status_code() == actix_web::http::StatusCode::UNAUTHORIZED {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        }
+        InternalError::from_response(rejection, response).into()
+    }
+}
+
+fn default_error_response(rejection: &AuthRejection) -> HttpResponse {
+    HttpResponse::build(rejection.status_code()).json(json!({
+        "error": {
+            "code": rejection.status_code().as_u16(),
+            "message": rejection.to_string(),
+        }
+    }))
+}
+
+pub(crate) fn auth_config(req: &HttpRequest) -> AuthConfig {
+    req.app_data::<AuthConfig>().cloned().unwrap_or_default()
+}
+
+/// Records whether the `AuthenticatedUser` in the request extensions was checked for
+/// revocation, so a stricter config verifies the token again.
+#[derive(Clone, Copy)]
+struct RevocationChecked(bool);
+
+pub(crate) async fn authenticate(
+    req: &HttpRequest,
+    config: &AuthConfig,
+) -> Result<AuthenticatedUser, AuthRejection> {
+    let cached = {
+        let extensions = req.extensions();
+        let revocation_checked = extensions
+            .get::<RevocationChecked>()
+            .is_some_and(|checked| checked.0);
+        extensions
+            .get::<AuthenticatedUser>()
+            .filter(|_| revocation_checked || !config.check_revoked)
+            .cloned()
+    };
+    let user = match cached {
+        Some(user) => user,
+        None => verify(req, config).await?,
+    };
+
+    if config.require_email_verified && user.claims.email_verified != Some(true) {
+        return Err(AuthRejection::EmailNotVerified);
+    }
+    Ok(user)
+}
+
+async fn verify(
+    req: &HttpRequest,
+    config: &AuthConfig,
+) -> Result<AuthenticatedUser, AuthRejection> {
+    let firebase = req
+        .app_data::<web::Data<Firebase>>()
+        .ok_or(AuthRejection::MissingFirebase)?;
+
+    let id_token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .ok_or(AuthRejection::MissingToken)?
+        .to_owned();
+
+    let options = VerifyOptions {
+        check_revoked: config.check_revoked,
+    };
+    let claims = firebase
+        .verify_id_token_with(&id_token, &options)
+        .await
+        .map_err(AuthRejection::InvalidToken)?;
+
+    let user = AuthenticatedUser { id_token, claims };
+    let mut extensions = req.extensions_mut();
+    extensions.insert(user.clone());
+    extensions.insert(RevocationChecked(config.check_revoked));
+    Ok(user)
+}
@@ -0,0 +1,86 @@
+use std::{
+    cell::RefCell,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use awc::{http::StatusCode, Client};
+use jsonwebtoken::{Algorithm, Header};
+
+use super::model::{AccessTokenClaims, AccessTokenResponse, ServiceAccount};
+use crate::error::{ErrorKind, FirebaseError};
+
+const ACCESS_TOKEN_SCOPES: &str =
+    "https://www.googleapis.com/auth/cloud-platform https://www.googleapis.com/auth/identitytoolkit";
+const ASSERTION_LIFETIME: Duration = Duration::from_secs(60 * 60);
+/// Access tokens are renewed this long before they expire.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// Exchanges a service account for OAuth access tokens and caches them until shortly
+/// before they expire.
+pub(crate) struct AdminCredentials {
+    service_account: ServiceAccount,
+    token: RefCell<Option<(String, Instant)>>,
+}
+
+impl AdminCredentials {
+    pub fn new(service_account: ServiceAccount) -> AdminCredentials {
+        AdminCredentials {
+            service_account,
+            token: RefCell::new(None),
+        }
+    }
+
+    pub async fn access_token<K: ErrorKind>(
+        &self,
+        client: &Client,
+    ) -> Result<String, FirebaseError<K>> {
+        if let Some((token, expires_at)) = &*self.token.borrow() {
+            if Instant::now() < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+
+        let assertion = self
+            .assertion()
+            .map_err(|_| FirebaseError::new(K::unknown()))?;
+        let mut response = client
+            .post(&self.service_account.token_uri)
+            .send_form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+            .await
+            .map_err(FirebaseError::send)?;
+        let status = response.status();
+        if status != StatusCode::OK {
+            return Err(FirebaseError::from_status(status));
+        }
+        let token = response
+            .json::<AccessTokenResponse>()
+            .await
+            .map_err(|err| FirebaseError::payload(status, err))?;
+
+        let lifetime = Duration::from_secs(token.expires_in).saturating_sub(EXPIRY_MARGIN);
+        *self.token.borrow_mut() = Some((token.access_token.clone(), Instant::now() + lifetime));
+        Ok(token.access_token)
+    }
+
+    /// The signed JWT presented to the token endpoint in place of a client secret.
+    fn assertion(&self) -> Result<String, jsonwebtoken::errors::Error> {
+        let iat = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let claims = AccessTokenClaims {
+            iss: &self.service_account.client_email,
+            scope: ACCESS_TOKEN_SCOPES,
+            aud: &self.service_account.token_uri,
+            iat,
+            exp: iat + ASSERTION_LIFETIME.as_secs(),
+        };
+
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(self.service_account.private_key_id.clone());
+        jsonwebtoken::encode(&header, &claims, &self.service_account.key)
+    }
+}
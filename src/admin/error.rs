@@ -0,0 +1,50 @@
+use std::{fmt, io};
+
+#[derive(Debug)]
+pub enum ServiceAccountError {
+    Io(io::Error),
+    DecodingError(serde_json::Error),
+    InvalidPrivateKey,
+}
+
+impl fmt::Display for ServiceAccountError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ServiceAccountError::Io(err) => write!(f, "{}", err),
+            ServiceAccountError::DecodingError(err) => write!(f, "{}", err),
+            ServiceAccountError::InvalidPrivateKey => write!(f, "Invalid private key"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CreateCustomTokenError {
+    InvalidUid,
+    ReservedClaim(String),
+    ClaimsTooLarge,
+    InvalidLifetime,
+    SigningError(jsonwebtoken::errors::Error),
+}
+
+impl fmt::Display for CreateCustomTokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CreateCustomTokenError::InvalidUid => {
+                write!(
+                    f,
+                    "Uid must be a non-empty string of at most 128 characters"
+                )
+            }
+            CreateCustomTokenError::ReservedClaim(claim) => {
+                write!(f, "Claim {} is reserved and cannot be set", claim)
+            }
+            CreateCustomTokenError::ClaimsTooLarge => {
+                write!(f, "Claims must not exceed 1000 bytes when serialized")
+            }
+            CreateCustomTokenError::InvalidLifetime => {
+                write!(f, "Custom tokens must expire within one hour")
+            }
+            CreateCustomTokenError::SigningError(err) => write!(f, "{}", err),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+pub(crate) mod credentials;
+pub mod error;
+pub mod model;
+
+pub use error::{CreateCustomTokenError, ServiceAccountError};
+pub use model::{CustomTokenOptions, ServiceAccount};
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use jsonwebtoken::{Algorithm, Header};
+
+use model::CustomTokenClaims;
+
+const CUSTOM_TOKEN_AUDIENCE: &str =
+    "https://identitytoolkit.googleapis.com/google.identity.identitytoolkit.v1.IdentityToolkit";
+const MAX_CUSTOM_TOKEN_LIFETIME: Duration = Duration::from_secs(60 * 60);
+const MAX_CLAIMS_SIZE: usize = 1000;
+const RESERVED_CLAIMS: [&str; 16] = [
+    "acr",
+    "amr",
+    "at_hash",
+    "aud",
+    "auth_time",
+    "azp",
+    "cnf",
+    "c_hash",
+    "exp",
+    "firebase",
+    "iat",
+    "iss",
+    "jti",
+    "nbf",
+    "nonce",
+    "sub",
+];
+
+impl ServiceAccount {
+    /// Creates a custom token for `uid`, to be exchanged with `Firebase::sign_in_with_custom_token`.
+    pub fn create_custom_token(
+        &self,
+        uid: &str,
+        options: &CustomTokenOptions,
+    ) -> Result<String, CreateCustomTokenError> {
+        if uid.is_empty() || uid.chars().count() > 128 {
+            return Err(CreateCustomTokenError::InvalidUid);
+        }
+        if options.expires_in.is_zero() || options.expires_in > MAX_CUSTOM_TOKEN_LIFETIME {
+            return Err(CreateCustomTokenError::InvalidLifetime);
+        }
+        if let Some(claims) = &options.claims {
+            if let Some(reserved) = claims
+                .keys()
+                .find(|key| RESERVED_CLAIMS.contains(&key.as_str()))
+            {
+                return Err(CreateCustomTokenError::ReservedClaim(reserved.clone()));
+            }
+            let size = serde_json::to_string(claims)
+                .map(|json| json.len())
+                .unwrap_or_default();
+            if size > MAX_CLAIMS_SIZE {
+                return Err(CreateCustomTokenError::ClaimsTooLarge);
+            }
+        }
+
+        let iat = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let claims = CustomTokenClaims {
+            iss: &self.client_email,
+            sub: &self.client_email,
+            aud: CUSTOM_TOKEN_AUDIENCE,
+            iat,
+            exp: iat + options.expires_in.as_secs(),
+            uid,
+            claims: options.claims.as_ref(),
+            tenant_id: options.tenant_id.as_deref(),
+        };
+
+        jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &self.key)
+            .map_err(CreateCustomTokenError::SigningError)
+    }
+}
@@ -0,0 +1,102 @@
+use std::{fs, path::Path, time::Duration};
+
+use jsonwebtoken::EncodingKey;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::error::ServiceAccountError;
+
+/// Credentials of a Google service account, as downloaded from the Firebase console.
+#[derive(Clone)]
+pub struct ServiceAccount {
+    pub project_id: String,
+    pub client_email: String,
+    pub private_key_id: String,
+    /// Where signed assertions are exchanged for OAuth access tokens.
+    pub token_uri: String,
+    pub(crate) key: EncodingKey,
+}
+
+#[derive(Deserialize)]
+struct ServiceAccountKey {
+    project_id: String,
+    client_email: String,
+    private_key_id: String,
+    private_key: String,
+    #[serde(default = "default_token_uri")]
+    token_uri: String,
+}
+
+fn default_token_uri() -> String {
+    "https://oauth2.googleapis.com/token".to_owned()
+}
+
+impl ServiceAccount {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<ServiceAccount, ServiceAccountError> {
+        let json = fs::read_to_string(path).map_err(ServiceAccountError::Io)?;
+        ServiceAccount::from_json(&json)
+    }
+
+    pub fn from_json(json: &str) -> Result<ServiceAccount, ServiceAccountError> {
+        let key = serde_json::from_str::<ServiceAccountKey>(json)
+            .map_err(ServiceAccountError::DecodingError)?;
+        let encoding_key = EncodingKey::from_rsa_pem(key.private_key.as_bytes())
+            .map_err(|_| ServiceAccountError::InvalidPrivateKey)?;
+
+        Ok(ServiceAccount {
+            project_id: key.project_id,
+            client_email: key.client_email,
+            private_key_id: key.private_key_id,
+            token_uri: key.token_uri,
+            key: encoding_key,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CustomTokenOptions {
+    /// Developer claims copied into the ID tokens of the signed in user.
+    pub claims: Option<Map<String, Value>>,
+    pub tenant_id: Option<String>,
+    /// Lifetime of the custom token itself, at most one hour.
+    pub expires_in: Duration,
+}
+
+impl Default for CustomTokenOptions {
+    fn default() -> Self {
+        CustomTokenOptions {
+            claims: None,
+            tenant_id: None,
+            expires_in: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct CustomTokenClaims<'a> {
+    pub iss: &'a str,
+    pub sub: &'a str,
+    pub aud: &'a str,
+    pub iat: u64,
+    pub exp: u64,
+    pub uid: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub claims: Option<&'a Map<String, Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct AccessTokenClaims<'a> {
+    pub iss: &'a str,
+    pub scope: &'a str,
+    pub aud: &'a str,
+    pub iat: u64,
+    pub exp: u64,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct AccessTokenResponse {
+    pub access_token: String,
+    pub expires_in: u64,
+}
@@ -1,24 +1,42 @@
+use awc::{
+    error::{JsonPayloadError, SendRequestError},
+    http::StatusCode,
+};
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{error, fmt, rc::Rc};
+
+use crate::model::MfaChallenge;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum LoginError {
     EmailNotFound,
     InvalidPassword,
+    /// Either the email or the password is wrong, with email enumeration protection enabled.
+    InvalidCredentials,
+    InvalidEmail,
+    MissingPassword,
+    InvalidIdpResponse,
     UserDisabled,
     OperationNotAllowed,
     TooManyAttempts,
+    /// The first factor was accepted, finish signing in with `Firebase::mfa_sign_in_finalize`.
+    MfaRequired(MfaChallenge),
     Unknown,
 }
 
 impl fmt::Display for LoginError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
+        match self {
             LoginError::EmailNotFound => write!(f, "Email not found"),
             LoginError::InvalidPassword => write!(f, "Invalid password"),
+            LoginError::InvalidCredentials => write!(f, "Invalid email or password"),
+            LoginError::InvalidEmail => write!(f, "Invalid email"),
+            LoginError::MissingPassword => write!(f, "Missing password"),
+            LoginError::InvalidIdpResponse => write!(f, "Invalid identity provider response"),
             LoginError::UserDisabled => write!(f, "User disabled"),
             LoginError::OperationNotAllowed => write!(f, "Operation not allowed"),
             LoginError::TooManyAttempts => write!(f, "Too many attempts"),
+            LoginError::MfaRequired(_) => write!(f, "Second factor required"),
             LoginError::Unknown => write!(f, "Unknown"),
         }
     }
@@ -27,6 +45,8 @@ impl fmt::Display for LoginError {
 #[derive(Debug)]
 pub enum RegisterError {
     EmailExists,
+    InvalidEmail,
+    WeakPassword,
     OperationNotAllowed,
     TooManyAttempts,
     Unknown,
@@ -37,6 +57,8 @@ impl fmt::Display for RegisterError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             RegisterError::EmailExists => write!(f, "Email exists"),
+            RegisterError::InvalidEmail => write!(f, "Invalid email"),
+            RegisterError::WeakPassword => write!(f, "Weak password"),
             RegisterError::OperationNotAllowed => write!(f, "Operation not allowed"),
             RegisterError::TooManyAttempts => write!(f, "Too many attempts"),
             RegisterError::Unknown => write!(f, "Unknown"),
@@ -49,6 +71,10 @@ impl fmt::Display for RegisterError {
 pub enum AccountError {
     InvalidIdToken,
     UserNotFound,
+    UserDisabled,
+    CredentialTooOld,
+    /// A profile update tried to delete an attribute that can't be deleted.
+    InvalidProfileAttribute,
     Unknown,
 }
 
@@ -57,12 +83,324 @@ impl fmt::Display for AccountError {
         match *self {
             AccountError::InvalidIdToken => write!(f, "Invalid Id token"),
             AccountError::UserNotFound => write!(f, "User not found"),
+            AccountError::UserDisabled => write!(f, "User disabled"),
+            AccountError::CredentialTooOld => {
+                write!(f, "Credential too old, the user has to sign in again")
+            }
+            AccountError::InvalidProfileAttribute => write!(f, "Invalid profile attribute"),
             AccountError::Unknown => write!(f, "Unknown error"),
         }
     }
 }
 
 #[derive(Debug)]
+pub enum LinkError {
+    EmailExists,
+    InvalidIdToken,
+    WeakPassword,
+    /// The provider account is already linked to another user.
+    FederatedUserIdAlreadyLinked,
+    CredentialTooOld,
+    TooManyAttempts,
+    Unknown,
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LinkError::EmailExists => write!(f, "Email exists"),
+            LinkError::InvalidIdToken => write!(f, "Invalid Id token"),
+            LinkError::WeakPassword => write!(f, "Weak password"),
+            LinkError::FederatedUserIdAlreadyLinked => {
+                write!(f, "Provider account already linked to another user")
+            }
+            LinkError::CredentialTooOld => {
+                write!(f, "Credential too old, the user has to sign in again")
+            }
+            LinkError::TooManyAttempts => write!(f, "Too many attempts"),
+            LinkError::Unknown => write!(f, "Unknown error"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum UpdateCredentialError {
+    EmailExists,
+    InvalidIdToken,
+    WeakPassword,
+    /// The user has to sign in again before changing sensitive account details.
+    CredentialTooOld,
+    InvalidEmail,
+    /// Users with a second factor have to change their email with
+    /// `Firebase::send_verify_and_change_email`.
+    EmailChangeNeedsVerification,
+    Unknown,
+}
+
+impl fmt::Display for UpdateCredentialError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UpdateCredentialError::EmailExists => write!(f, "Email exists"),
+            UpdateCredentialError::InvalidIdToken => write!(f, "Invalid Id token"),
+            UpdateCredentialError::WeakPassword => write!(f, "Weak password"),
+            UpdateCredentialError::CredentialTooOld => {
+                write!(f, "Credential too old, the user has to sign in again")
+            }
+            UpdateCredentialError::InvalidEmail => write!(f, "Invalid email"),
+            UpdateCredentialError::EmailChangeNeedsVerification => {
+                write!(f, "Email change needs verification")
+            }
+            UpdateCredentialError::Unknown => write!(f, "Unknown error"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CustomTokenError {
+    InvalidCustomToken,
+    CredentialMismatch,
+    UserDisabled,
+    TooManyAttempts,
+    Unknown,
+}
+
+impl fmt::Display for CustomTokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CustomTokenError::InvalidCustomToken => write!(f, "Invalid custom token"),
+            CustomTokenError::CredentialMismatch => write!(f, "Credential mismatch"),
+            CustomTokenError::UserDisabled => write!(f, "User disabled"),
+            CustomTokenError::TooManyAttempts => write!(f, "Too many attempts"),
+            CustomTokenError::Unknown => write!(f, "Unknown error"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PasswordResetError {
+    EmailNotFound,
+    ExpiredOobCode,
+    InvalidOobCode,
+    UserDisabled,
+    WeakPassword,
+    OperationNotAllowed,
+    TooManyAttempts,
+    Unknown,
+}
+
+impl fmt::Display for PasswordResetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PasswordResetError::EmailNotFound => write!(f, "Email not found"),
+            PasswordResetError::ExpiredOobCode => write!(f, "Expired action code"),
+            PasswordResetError::InvalidOobCode => write!(f, "Invalid action code"),
+            PasswordResetError::UserDisabled => write!(f, "User disabled"),
+            PasswordResetError::WeakPassword => write!(f, "Weak password"),
+            PasswordResetError::OperationNotAllowed => write!(f, "Operation not allowed"),
+            PasswordResetError::TooManyAttempts => write!(f, "Too many attempts"),
+            PasswordResetError::Unknown => write!(f, "Unknown error"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum EmailLinkError {
+    InvalidEmail,
+    ExpiredOobCode,
+    InvalidOobCode,
+    UserDisabled,
+    OperationNotAllowed,
+    TooManyAttempts,
+    Unknown,
+}
+
+impl fmt::Display for EmailLinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EmailLinkError::InvalidEmail => write!(f, "Invalid email"),
+            EmailLinkError::ExpiredOobCode => write!(f, "Expired action code"),
+            EmailLinkError::InvalidOobCode => write!(f, "Invalid action code"),
+            EmailLinkError::UserDisabled => write!(f, "User disabled"),
+            EmailLinkError::OperationNotAllowed => write!(f, "Operation not allowed"),
+            EmailLinkError::TooManyAttempts => write!(f, "Too many attempts"),
+            EmailLinkError::Unknown => write!(f, "Unknown error"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ActionCodeError {
+    ExpiredOobCode,
+    InvalidOobCode,
+    EmailExists,
+    UserDisabled,
+    UserNotFound,
+    Unknown,
+}
+
+impl fmt::Display for ActionCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ActionCodeError::ExpiredOobCode => write!(f, "Expired action code"),
+            ActionCodeError::InvalidOobCode => write!(f, "Invalid action code"),
+            ActionCodeError::EmailExists => write!(f, "Email exists"),
+            ActionCodeError::UserDisabled => write!(f, "User disabled"),
+            ActionCodeError::UserNotFound => write!(f, "User not found"),
+            ActionCodeError::Unknown => write!(f, "Unknown error"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SignInMethodsError {
+    InvalidEmail,
+    InvalidContinueUri,
+    Unknown,
+}
+
+impl fmt::Display for SignInMethodsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SignInMethodsError::InvalidEmail => write!(f, "Invalid email"),
+            SignInMethodsError::InvalidContinueUri => write!(f, "Invalid continue URI"),
+            SignInMethodsError::Unknown => write!(f, "Unknown error"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PhoneAuthError {
+    /// Also returned without a request when the number is not in E.164 format.
+    InvalidPhoneNumber,
+    /// The phone number already belongs to another user.
+    PhoneNumberExists,
+    CaptchaCheckFailed,
+    InvalidCode,
+    InvalidSessionInfo,
+    SessionExpired,
+    QuotaExceeded,
+    InvalidIdToken,
+    TooManyAttempts,
+    Unknown,
+}
+
+impl fmt::Display for PhoneAuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PhoneAuthError::InvalidPhoneNumber => write!(f, "Invalid phone number"),
+            PhoneAuthError::PhoneNumberExists => write!(f, "Phone number exists"),
+            PhoneAuthError::CaptchaCheckFailed => write!(f, "reCAPTCHA check failed"),
+            PhoneAuthError::InvalidCode => write!(f, "Invalid verification code"),
+            PhoneAuthError::InvalidSessionInfo => write!(f, "Invalid session info"),
+            PhoneAuthError::SessionExpired => write!(f, "Verification code expired"),
+            PhoneAuthError::QuotaExceeded => write!(f, "SMS quota exceeded"),
+            PhoneAuthError::InvalidIdToken => write!(f, "Invalid Id token"),
+            PhoneAuthError::TooManyAttempts => write!(f, "Too many attempts"),
+            PhoneAuthError::Unknown => write!(f, "Unknown error"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MfaError {
+    InvalidPendingCredential,
+    EnrollmentNotFound,
+    InvalidIdToken,
+    InvalidPhoneNumber,
+    CaptchaCheckFailed,
+    InvalidCode,
+    SessionExpired,
+    QuotaExceeded,
+    /// Second factors can only be enrolled by users with a verified email.
+    UnverifiedEmail,
+    SecondFactorExists,
+    SecondFactorLimitExceeded,
+    CredentialTooOld,
+    /// Firebase rejected the request as malformed (`INVALID_ARGUMENT`).
+    InvalidArgument,
+    Unknown,
+}
+
+impl fmt::Display for MfaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MfaError::InvalidPendingCredential => write!(f, "Invalid MFA pending credential"),
+            MfaError::EnrollmentNotFound => write!(f, "MFA enrollment not found"),
+            MfaError::InvalidIdToken => write!(f, "Invalid Id token"),
+            MfaError::InvalidPhoneNumber => write!(f, "Invalid phone number"),
+            MfaError::CaptchaCheckFailed => write!(f, "reCAPTCHA check failed"),
+            MfaError::InvalidCode => write!(f, "Invalid verification code"),
+            MfaError::SessionExpired => write!(f, "Verification code expired"),
+            MfaError::QuotaExceeded => write!(f, "SMS quota exceeded"),
+            MfaError::UnverifiedEmail => write!(f, "Email not verified"),
+            MfaError::SecondFactorExists => write!(f, "Second factor already enrolled"),
+            MfaError::SecondFactorLimitExceeded => write!(f, "Too many second factors"),
+            MfaError::CredentialTooOld => {
+                write!(f, "Credential too old, the user has to sign in again")
+            }
+            MfaError::InvalidArgument => write!(f, "Invalid argument"),
+            MfaError::Unknown => write!(f, "Unknown error"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SessionCookieError {
+    InvalidIdToken,
+    /// Also returned without a request when the duration is outside of 5 minutes to 2 weeks.
+    InvalidDuration,
+    /// The project id is needed to address the project's admin endpoints.
+    MissingProjectId,
+    /// Neither a service account nor the emulator is set, so no request was sent.
+    MissingCredentials,
+    /// The admin credentials were rejected or lack the required permissions.
+    InsufficientPermission,
+    UserNotFound,
+    Unknown,
+}
+
+impl fmt::Display for SessionCookieError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SessionCookieError::InvalidIdToken => write!(f, "Invalid Id token"),
+            SessionCookieError::InvalidDuration => write!(f, "Invalid session cookie duration"),
+            SessionCookieError::MissingProjectId => write!(f, "Missing project id"),
+            SessionCookieError::MissingCredentials => write!(f, "Missing admin credentials"),
+            SessionCookieError::InsufficientPermission => write!(f, "Insufficient permission"),
+            SessionCookieError::UserNotFound => write!(f, "User not found"),
+            SessionCookieError::Unknown => write!(f, "Unknown error"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum UserManagementError {
+    UserNotFound,
+    /// The uid is empty or longer than 128 characters.
+    InvalidUid,
+    /// The project id is needed to address the project's admin endpoints.
+    MissingProjectId,
+    /// Neither a service account nor the emulator is set, so no request was sent.
+    MissingCredentials,
+    /// The admin credentials were rejected or lack the required permissions.
+    InsufficientPermission,
+    Unknown,
+}
+
+impl fmt::Display for UserManagementError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UserManagementError::UserNotFound => write!(f, "User not found"),
+            UserManagementError::InvalidUid => write!(f, "Invalid uid"),
+            UserManagementError::MissingProjectId => write!(f, "Missing project id"),
+            UserManagementError::MissingCredentials => write!(f, "Missing admin credentials"),
+            UserManagementError::InsufficientPermission => write!(f, "Insufficient permission"),
+            UserManagementError::Unknown => write!(f, "Unknown error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum RefreshTokenError {
     TokenExpired,
     UserDisabled,
@@ -85,15 +423,18 @@ impl fmt::Display for RefreshTokenError {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct ErrorBody {
     domain: String,
     reason: String,
     message: String,
 }
 
-#[derive(Serialize, Deserialize)]
+/// The error object Firebase sends back for a failed request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Error {
+    /// Missing from Secure Token API errors.
+    #[serde(default)]
     errors: Vec<ErrorBody>,
     code: i32,
     message: String,
@@ -105,41 +446,575 @@ pub struct ErrorContainer {
 }
 
 impl Error {
+    /// The HTTP status code, as reported in the body.
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+
+    /// The Firebase error code, e.g. `EMAIL_EXISTS`, possibly followed by a description.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The `reason` of each of the `errors`, e.g. `invalid`.
+    pub fn reasons(&self) -> impl Iterator<Item = &str> {
+        self.errors.iter().map(|error| error.reason.as_str())
+    }
+}
+
+/// The error of a request to Firebase: a friendly `kind` such as `LoginError::EmailNotFound`,
+/// along with whatever is known about the underlying failure.
+#[derive(Debug, Clone)]
+pub struct FirebaseError<K> {
+    kind: K,
+    status: Option<StatusCode>,
+    response: Option<Error>,
+    source: Option<Rc<ErrorSource>>,
+}
+
+#[derive(Debug)]
+enum ErrorSource {
+    Send(SendRequestError),
+    Payload(JsonPayloadError),
+}
+
+impl<K> FirebaseError<K> {
+    /// An error detected without, or despite, a successful response.
+    pub(crate) fn new(kind: K) -> FirebaseError<K> {
+        FirebaseError {
+            kind,
+            status: None,
+            response: None,
+            source: None,
+        }
+    }
+
+    pub fn kind(&self) -> &K {
+        &self.kind
+    }
+
+    pub fn into_kind(self) -> K {
+        self.kind
+    }
+
+    /// The status of the HTTP response, if one was received.
+    pub fn status(&self) -> Option<StatusCode> {
+        self.status
+    }
+
+    /// The error Firebase sent back, if the response contained one.
+    pub fn response(&self) -> Option<&Error> {
+        self.response.as_ref()
+    }
+
+    /// The transport error, if the request could not be sent.
+    pub fn send_error(&self) -> Option<&SendRequestError> {
+        match self.source.as_deref() {
+            Some(ErrorSource::Send(err)) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// The decoding error, if the response body was not what Firebase documents.
+    pub fn payload_error(&self) -> Option<&JsonPayloadError> {
+        match self.source.as_deref() {
+            Some(ErrorSource::Payload(err)) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl<K: ErrorKind> FirebaseError<K> {
+    pub(crate) fn send(err: SendRequestError) -> FirebaseError<K> {
+        FirebaseError {
+            source: Some(Rc::new(ErrorSource::Send(err))),
+            ..FirebaseError::new(K::unknown())
+        }
+    }
+
+    pub(crate) fn payload(status: StatusCode, err: JsonPayloadError) -> FirebaseError<K> {
+        FirebaseError {
+            status: Some(status),
+            source: Some(Rc::new(ErrorSource::Payload(err))),
+            ..FirebaseError::new(K::unknown())
+        }
+    }
+
+    /// A failed response without a Firebase error in its body.
+    pub(crate) fn from_status(status: StatusCode) -> FirebaseError<K> {
+        FirebaseError {
+            status: Some(status),
+            ..FirebaseError::new(K::unknown())
+        }
+    }
+
+    pub(crate) fn from_response(status: StatusCode, error: Error) -> FirebaseError<K> {
+        let kind = K::from_error(&error);
+        FirebaseError {
+            status: Some(status),
+            response: Some(error),
+            ..FirebaseError::new(kind)
+        }
+    }
+}
+
+impl<K: fmt::Display> fmt::Display for FirebaseError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        match (&self.response, self.source.as_deref()) {
+            (Some(error), _) => write!(f, ": {}", error.message),
+            (None, Some(ErrorSource::Send(err))) => write!(f, ": {}", err),
+            (None, Some(ErrorSource::Payload(err))) => write!(f, ": {}", err),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl<K: fmt::Debug + fmt::Display> error::Error for FirebaseError<K> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self.source.as_deref() {
+            Some(ErrorSource::Send(err)) => Some(err),
+            Some(ErrorSource::Payload(err)) => Some(err),
+            None => None,
+        }
+    }
+}
+
+/// A friendly error enum that a Firebase error response can be mapped to.
+pub trait ErrorKind {
+    fn unknown() -> Self;
+    fn from_error(error: &Error) -> Self;
+}
+
+macro_rules! error_kind {
+    ($kind:ident, $mapping:ident) => {
+        impl ErrorKind for $kind {
+            fn unknown() -> Self {
+                $kind::Unknown
+            }
+
+            fn from_error(error: &Error) -> Self {
+                error.$mapping()
+            }
+        }
+    };
+}
+
+error_kind!(LoginError, login_error);
+error_kind!(RegisterError, register_error);
+error_kind!(AccountError, account_error);
+error_kind!(LinkError, link_error);
+error_kind!(UpdateCredentialError, update_credential_error);
+error_kind!(CustomTokenError, custom_token_error);
+error_kind!(PasswordResetError, password_reset_error);
+error_kind!(EmailLinkError, email_link_error);
+error_kind!(ActionCodeError, action_code_error);
+error_kind!(SignInMethodsError, sign_in_methods_error);
+error_kind!(PhoneAuthError, phone_auth_error);
+error_kind!(MfaError, mfa_error);
+error_kind!(SessionCookieError, session_cookie_error);
+error_kind!(UserManagementError, user_management_error);
+error_kind!(RefreshTokenError, refresh_token_error);
+
+/// An error kind of the admin endpoints, which need a project id and admin credentials.
+pub(crate) trait AdminErrorKind: ErrorKind {
+    fn missing_project_id() -> Self;
+    fn missing_credentials() -> Self;
+}
+
+macro_rules! admin_error_kind {
+    ($kind:ident) => {
+        impl AdminErrorKind for $kind {
+            fn missing_project_id() -> Self {
+                $kind::MissingProjectId
+            }
+
+            fn missing_credentials() -> Self {
+                $kind::MissingCredentials
+            }
+        }
+    };
+}
+
+admin_error_kind!(SessionCookieError);
+admin_error_kind!(UserManagementError);
+
+/// Declares `AuthErrorCode` along with the strings Firebase sends for each code.
+macro_rules! auth_error_codes {
+    ($($(#[$meta:meta])* $variant:ident => $code:literal,)*) => {
+        /// The error codes documented for the Identity Toolkit and Secure Token APIs.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum AuthErrorCode {
+            $($(#[$meta])* $variant,)*
+            /// A code this crate doesn't know about yet.
+            Other(String),
+        }
+
+        impl AuthErrorCode {
+            fn from_code(code: &str) -> AuthErrorCode {
+                match code {
+                    $($code => AuthErrorCode::$variant,)*
+                    _ => AuthErrorCode::Other(code.to_owned()),
+                }
+            }
+
+            /// The code as sent by Firebase, e.g. `EMAIL_EXISTS`.
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(AuthErrorCode::$variant => $code,)*
+                    AuthErrorCode::Other(code) => code,
+                }
+            }
+        }
+    };
+}
+
+auth_error_codes! {
+    AdminOnlyOperation => "ADMIN_ONLY_OPERATION",
+    CaptchaCheckFailed => "CAPTCHA_CHECK_FAILED",
+    CredentialMismatch => "CREDENTIAL_MISMATCH",
+    CredentialTooOldLoginAgain => "CREDENTIAL_TOO_OLD_LOGIN_AGAIN",
+    EmailChangeNeedsVerification => "EMAIL_CHANGE_NEEDS_VERIFICATION",
+    EmailExists => "EMAIL_EXISTS",
+    EmailNotFound => "EMAIL_NOT_FOUND",
+    ExpiredOobCode => "EXPIRED_OOB_CODE",
+    FederatedUserIdAlreadyLinked => "FEDERATED_USER_ID_ALREADY_LINKED",
+    InsufficientPermission => "INSUFFICIENT_PERMISSION",
+    InvalidAppCredential => "INVALID_APP_CREDENTIAL",
+    InvalidArgument => "INVALID_ARGUMENT",
+    InvalidCode => "INVALID_CODE",
+    InvalidContinueUri => "INVALID_CONTINUE_URI",
+    InvalidCustomToken => "INVALID_CUSTOM_TOKEN",
+    InvalidDeleteAttribute => "INVALID_DELETE_ATTRIBUTE",
+    InvalidDynamicLinkDomain => "INVALID_DYNAMIC_LINK_DOMAIN",
+    InvalidEmail => "INVALID_EMAIL",
+    InvalidGrantType => "INVALID_GRANT_TYPE",
+    InvalidIdToken => "INVALID_ID_TOKEN",
+    InvalidIdpResponse => "INVALID_IDP_RESPONSE",
+    /// Sent instead of `EMAIL_NOT_FOUND` and `INVALID_PASSWORD` when email enumeration
+    /// protection is enabled.
+    InvalidLoginCredentials => "INVALID_LOGIN_CREDENTIALS",
+    InvalidMfaPendingCredential => "INVALID_MFA_PENDING_CREDENTIAL",
+    InvalidOobCode => "INVALID_OOB_CODE",
+    InvalidPassword => "INVALID_PASSWORD",
+    InvalidPendingToken => "INVALID_PENDING_TOKEN",
+    InvalidPhoneNumber => "INVALID_PHONE_NUMBER",
+    InvalidProviderId => "INVALID_PROVIDER_ID",
+    InvalidRecaptchaToken => "INVALID_RECAPTCHA_TOKEN",
+    InvalidRefreshToken => "INVALID_REFRESH_TOKEN",
+    InvalidReqType => "INVALID_REQ_TYPE",
+    InvalidSessionCookieDuration => "INVALID_SESSION_COOKIE_DURATION",
+    InvalidSessionInfo => "INVALID_SESSION_INFO",
+    InvalidTenantId => "INVALID_TENANT_ID",
+    InvalidVerificationCode => "INVALID_VERIFICATION_CODE",
+    MfaEnrollmentNotFound => "MFA_ENROLLMENT_NOT_FOUND",
+    MissingClientIdentifier => "MISSING_CLIENT_IDENTIFIER",
+    MissingCode => "MISSING_CODE",
+    MissingContinueUri => "MISSING_CONTINUE_URI",
+    MissingCustomToken => "MISSING_CUSTOM_TOKEN",
+    MissingEmail => "MISSING_EMAIL",
+    MissingIdToken => "MISSING_ID_TOKEN",
+    MissingIdentifier => "MISSING_IDENTIFIER",
+    MissingLocalId => "MISSING_LOCAL_ID",
+    MissingMfaEnrollmentId => "MISSING_MFA_ENROLLMENT_ID",
+    MissingMfaPendingCredential => "MISSING_MFA_PENDING_CREDENTIAL",
+    MissingNewEmail => "MISSING_NEW_EMAIL",
+    MissingOobCode => "MISSING_OOB_CODE",
+    MissingPassword => "MISSING_PASSWORD",
+    MissingPhoneNumber => "MISSING_PHONE_NUMBER",
+    MissingRecaptchaToken => "MISSING_RECAPTCHA_TOKEN",
+    MissingRefreshToken => "MISSING_REFRESH_TOKEN",
+    MissingReqType => "MISSING_REQ_TYPE",
+    MissingSessionInfo => "MISSING_SESSION_INFO",
+    OperationNotAllowed => "OPERATION_NOT_ALLOWED",
+    PasswordLoginDisabled => "PASSWORD_LOGIN_DISABLED",
+    PhoneNumberExists => "PHONE_NUMBER_EXISTS",
+    ProjectNotFound => "PROJECT_NOT_FOUND",
+    QuotaExceeded => "QUOTA_EXCEEDED",
+    ResetPasswordExceedLimit => "RESET_PASSWORD_EXCEED_LIMIT",
+    SecondFactorExists => "SECOND_FACTOR_EXISTS",
+    SecondFactorLimitExceeded => "SECOND_FACTOR_LIMIT_EXCEEDED",
+    SessionExpired => "SESSION_EXPIRED",
+    TenantIdMismatch => "TENANT_ID_MISMATCH",
+    TokenExpired => "TOKEN_EXPIRED",
+    TooManyAttemptsTryLater => "TOO_MANY_ATTEMPTS_TRY_LATER",
+    Unauthenticated => "UNAUTHENTICATED",
+    UnauthorizedDomain => "UNAUTHORIZED_DOMAIN",
+    UnverifiedEmail => "UNVERIFIED_EMAIL",
+    UserDisabled => "USER_DISABLED",
+    UserNotFound => "USER_NOT_FOUND",
+    WeakPassword => "WEAK_PASSWORD",
+}
+
+impl AuthErrorCode {
+    /// Splits a message such as `WEAK_PASSWORD : Password should be at least 6 characters`
+    /// into its code and the description following it.
+    pub fn parse(message: &str) -> (AuthErrorCode, Option<&str>) {
+        let (code, detail) = match message.split_once(" : ") {
+            Some((code, detail)) => (code.trim(), Some(detail.trim())),
+            None => (message.trim(), None),
+        };
+        (AuthErrorCode::from_code(code), detail)
+    }
+}
+
+impl fmt::Display for AuthErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Error {
+    /// The error code at the start of the `message`.
+    pub fn auth_code(&self) -> AuthErrorCode {
+        AuthErrorCode::parse(&self.message).0
+    }
+
+    /// The description following the error code, if any.
+    pub fn detail(&self) -> Option<&str> {
+        AuthErrorCode::parse(&self.message).1
+    }
+
     pub fn register_error(&self) -> RegisterError {
-        match self.message.as_str() {
-            "EMAIL_EXISTS" => RegisterError::EmailExists,
-            "OPERATION_NOT_ALLOWED" => RegisterError::OperationNotAllowed,
-            "TOO_MANY_ATTEMPTS_TRY_LATER" => RegisterError::TooManyAttempts,
+        match self.auth_code() {
+            AuthErrorCode::EmailExists => RegisterError::EmailExists,
+            AuthErrorCode::InvalidEmail | AuthErrorCode::MissingEmail => {
+                RegisterError::InvalidEmail
+            }
+            AuthErrorCode::MissingPassword => RegisterError::MissingPassword,
+            AuthErrorCode::WeakPassword => RegisterError::WeakPassword,
+            AuthErrorCode::OperationNotAllowed | AuthErrorCode::AdminOnlyOperation => {
+                RegisterError::OperationNotAllowed
+            }
+            AuthErrorCode::TooManyAttemptsTryLater => RegisterError::TooManyAttempts,
             _ => RegisterError::Unknown,
         }
     }
 
     pub fn login_error(&self) -> LoginError {
-        match self.message.as_str() {
-            "EMAIL_NOT_FOUND" => LoginError::EmailNotFound,
-            "INVALID_PASSWORD" => LoginError::InvalidPassword,
-            "USER_DISABLED" => LoginError::UserDisabled,
-            "OPERATION_NOT_ALLOWED" => LoginError::OperationNotAllowed,
-            "TOO_MANY_ATTEMPTS_TRY_LATER" => LoginError::TooManyAttempts,
+        match self.auth_code() {
+            AuthErrorCode::EmailNotFound => LoginError::EmailNotFound,
+            AuthErrorCode::InvalidPassword => LoginError::InvalidPassword,
+            AuthErrorCode::InvalidLoginCredentials => LoginError::InvalidCredentials,
+            AuthErrorCode::InvalidEmail | AuthErrorCode::MissingEmail => LoginError::InvalidEmail,
+            AuthErrorCode::MissingPassword => LoginError::MissingPassword,
+            AuthErrorCode::InvalidIdpResponse => LoginError::InvalidIdpResponse,
+            AuthErrorCode::UserDisabled => LoginError::UserDisabled,
+            AuthErrorCode::OperationNotAllowed | AuthErrorCode::PasswordLoginDisabled => {
+                LoginError::OperationNotAllowed
+            }
+            AuthErrorCode::TooManyAttemptsTryLater => LoginError::TooManyAttempts,
             _ => LoginError::Unknown,
         }
     }
 
     pub fn account_error(&self) -> AccountError {
-        match self.message.as_str() {
-            "INVALID_ID_TOKEN" => AccountError::InvalidIdToken,
-            "USER_NOT_FOUND" => AccountError::UserNotFound,
+        match self.auth_code() {
+            AuthErrorCode::InvalidIdToken
+            | AuthErrorCode::MissingIdToken
+            | AuthErrorCode::TokenExpired => AccountError::InvalidIdToken,
+            AuthErrorCode::UserNotFound => AccountError::UserNotFound,
+            AuthErrorCode::UserDisabled => AccountError::UserDisabled,
+            AuthErrorCode::CredentialTooOldLoginAgain => AccountError::CredentialTooOld,
+            AuthErrorCode::InvalidDeleteAttribute => AccountError::InvalidProfileAttribute,
             _ => AccountError::Unknown,
         }
     }
 
+    pub fn link_error(&self) -> LinkError {
+        match self.auth_code() {
+            AuthErrorCode::EmailExists => LinkError::EmailExists,
+            AuthErrorCode::InvalidIdToken | AuthErrorCode::TokenExpired => {
+                LinkError::InvalidIdToken
+            }
+            AuthErrorCode::FederatedUserIdAlreadyLinked => LinkError::FederatedUserIdAlreadyLinked,
+            AuthErrorCode::CredentialTooOldLoginAgain => LinkError::CredentialTooOld,
+            AuthErrorCode::WeakPassword => LinkError::WeakPassword,
+            AuthErrorCode::TooManyAttemptsTryLater => LinkError::TooManyAttempts,
+            _ => LinkError::Unknown,
+        }
+    }
+
+    pub fn update_credential_error(&self) -> UpdateCredentialError {
+        match self.auth_code() {
+            AuthErrorCode::EmailExists => UpdateCredentialError::EmailExists,
+            AuthErrorCode::InvalidIdToken | AuthErrorCode::TokenExpired => {
+                UpdateCredentialError::InvalidIdToken
+            }
+            AuthErrorCode::CredentialTooOldLoginAgain => UpdateCredentialError::CredentialTooOld,
+            AuthErrorCode::WeakPassword => UpdateCredentialError::WeakPassword,
+            AuthErrorCode::InvalidEmail | AuthErrorCode::MissingNewEmail => {
+                UpdateCredentialError::InvalidEmail
+            }
+            AuthErrorCode::EmailChangeNeedsVerification => {
+                UpdateCredentialError::EmailChangeNeedsVerification
+            }
+            _ => UpdateCredentialError::Unknown,
+        }
+    }
+
+    pub fn custom_token_error(&self) -> CustomTokenError {
+        match self.auth_code() {
+            AuthErrorCode::InvalidCustomToken | AuthErrorCode::MissingCustomToken => {
+                CustomTokenError::InvalidCustomToken
+            }
+            AuthErrorCode::CredentialMismatch => CustomTokenError::CredentialMismatch,
+            AuthErrorCode::UserDisabled => CustomTokenError::UserDisabled,
+            AuthErrorCode::TooManyAttemptsTryLater => CustomTokenError::TooManyAttempts,
+            _ => CustomTokenError::Unknown,
+        }
+    }
+
+    pub fn password_reset_error(&self) -> PasswordResetError {
+        match self.auth_code() {
+            AuthErrorCode::EmailNotFound => PasswordResetError::EmailNotFound,
+            AuthErrorCode::ExpiredOobCode => PasswordResetError::ExpiredOobCode,
+            AuthErrorCode::InvalidOobCode | AuthErrorCode::MissingOobCode => {
+                PasswordResetError::InvalidOobCode
+            }
+            AuthErrorCode::UserDisabled => PasswordResetError::UserDisabled,
+            AuthErrorCode::OperationNotAllowed => PasswordResetError::OperationNotAllowed,
+            AuthErrorCode::TooManyAttemptsTryLater | AuthErrorCode::ResetPasswordExceedLimit => {
+                PasswordResetError::TooManyAttempts
+            }
+            AuthErrorCode::WeakPassword => PasswordResetError::WeakPassword,
+            _ => PasswordResetError::Unknown,
+        }
+    }
+
+    pub fn email_link_error(&self) -> EmailLinkError {
+        match self.auth_code() {
+            AuthErrorCode::InvalidEmail | AuthErrorCode::MissingEmail => {
+                EmailLinkError::InvalidEmail
+            }
+            AuthErrorCode::ExpiredOobCode => EmailLinkError::ExpiredOobCode,
+            AuthErrorCode::InvalidOobCode | AuthErrorCode::MissingOobCode => {
+                EmailLinkError::InvalidOobCode
+            }
+            AuthErrorCode::UserDisabled => EmailLinkError::UserDisabled,
+            AuthErrorCode::OperationNotAllowed => EmailLinkError::OperationNotAllowed,
+            AuthErrorCode::TooManyAttemptsTryLater => EmailLinkError::TooManyAttempts,
+            _ => EmailLinkError::Unknown,
+        }
+    }
+
+    pub fn action_code_error(&self) -> ActionCodeError {
+        match self.auth_code() {
+            AuthErrorCode::ExpiredOobCode => ActionCodeError::ExpiredOobCode,
+            AuthErrorCode::InvalidOobCode | AuthErrorCode::MissingOobCode => {
+                ActionCodeError::InvalidOobCode
+            }
+            AuthErrorCode::EmailExists => ActionCodeError::EmailExists,
+            AuthErrorCode::UserDisabled => ActionCodeError::UserDisabled,
+            AuthErrorCode::UserNotFound => ActionCodeError::UserNotFound,
+            _ => ActionCodeError::Unknown,
+        }
+    }
+
+    pub fn sign_in_methods_error(&self) -> SignInMethodsError {
+        match self.auth_code() {
+            AuthErrorCode::InvalidEmail | AuthErrorCode::MissingIdentifier => {
+                SignInMethodsError::InvalidEmail
+            }
+            AuthErrorCode::InvalidContinueUri | AuthErrorCode::MissingContinueUri => {
+                SignInMethodsError::InvalidContinueUri
+            }
+            _ => SignInMethodsError::Unknown,
+        }
+    }
+
+    pub fn phone_auth_error(&self) -> PhoneAuthError {
+        match self.auth_code() {
+            AuthErrorCode::InvalidPhoneNumber | AuthErrorCode::MissingPhoneNumber => {
+                PhoneAuthError::InvalidPhoneNumber
+            }
+            AuthErrorCode::PhoneNumberExists => PhoneAuthError::PhoneNumberExists,
+            AuthErrorCode::CaptchaCheckFailed
+            | AuthErrorCode::InvalidAppCredential
+            | AuthErrorCode::InvalidRecaptchaToken
+            | AuthErrorCode::MissingClientIdentifier
+            | AuthErrorCode::MissingRecaptchaToken => PhoneAuthError::CaptchaCheckFailed,
+            AuthErrorCode::InvalidCode
+            | AuthErrorCode::InvalidVerificationCode
+            | AuthErrorCode::MissingCode => PhoneAuthError::InvalidCode,
+            AuthErrorCode::InvalidSessionInfo | AuthErrorCode::MissingSessionInfo => {
+                PhoneAuthError::InvalidSessionInfo
+            }
+            AuthErrorCode::SessionExpired => PhoneAuthError::SessionExpired,
+            AuthErrorCode::QuotaExceeded => PhoneAuthError::QuotaExceeded,
+            AuthErrorCode::InvalidIdToken | AuthErrorCode::TokenExpired => {
+                PhoneAuthError::InvalidIdToken
+            }
+            AuthErrorCode::TooManyAttemptsTryLater => PhoneAuthError::TooManyAttempts,
+            _ => PhoneAuthError::Unknown,
+        }
+    }
+
+    pub fn mfa_error(&self) -> MfaError {
+        match self.auth_code() {
+            AuthErrorCode::InvalidPendingToken
+            | AuthErrorCode::InvalidMfaPendingCredential
+            | AuthErrorCode::MissingMfaPendingCredential => MfaError::InvalidPendingCredential,
+            AuthErrorCode::MfaEnrollmentNotFound | AuthErrorCode::MissingMfaEnrollmentId => {
+                MfaError::EnrollmentNotFound
+            }
+            AuthErrorCode::InvalidIdToken | AuthErrorCode::TokenExpired => MfaError::InvalidIdToken,
+            AuthErrorCode::InvalidPhoneNumber | AuthErrorCode::MissingPhoneNumber => {
+                MfaError::InvalidPhoneNumber
+            }
+            AuthErrorCode::CaptchaCheckFailed
+            | AuthErrorCode::InvalidAppCredential
+            | AuthErrorCode::InvalidRecaptchaToken
+            | AuthErrorCode::MissingClientIdentifier
+            | AuthErrorCode::MissingRecaptchaToken => MfaError::CaptchaCheckFailed,
+            AuthErrorCode::InvalidCode
+            | AuthErrorCode::InvalidVerificationCode
+            | AuthErrorCode::MissingCode => MfaError::InvalidCode,
+            AuthErrorCode::SessionExpired => MfaError::SessionExpired,
+            AuthErrorCode::QuotaExceeded => MfaError::QuotaExceeded,
+            AuthErrorCode::UnverifiedEmail => MfaError::UnverifiedEmail,
+            AuthErrorCode::SecondFactorExists => MfaError::SecondFactorExists,
+            AuthErrorCode::SecondFactorLimitExceeded => MfaError::SecondFactorLimitExceeded,
+            AuthErrorCode::CredentialTooOldLoginAgain => MfaError::CredentialTooOld,
+            AuthErrorCode::InvalidArgument => MfaError::InvalidArgument,
+            _ => MfaError::Unknown,
+        }
+    }
+
+    pub fn session_cookie_error(&self) -> SessionCookieError {
+        match self.auth_code() {
+            AuthErrorCode::InvalidIdToken
+            | AuthErrorCode::MissingIdToken
+            | AuthErrorCode::TokenExpired => SessionCookieError::InvalidIdToken,
+            AuthErrorCode::InvalidSessionCookieDuration => SessionCookieError::InvalidDuration,
+            AuthErrorCode::InsufficientPermission
+            | AuthErrorCode::ProjectNotFound
+            | AuthErrorCode::Unauthenticated => SessionCookieError::InsufficientPermission,
+            AuthErrorCode::UserNotFound => SessionCookieError::UserNotFound,
+            _ => SessionCookieError::Unknown,
+        }
+    }
+
+    pub fn user_management_error(&self) -> UserManagementError {
+        match self.auth_code() {
+            AuthErrorCode::UserNotFound => UserManagementError::UserNotFound,
+            AuthErrorCode::MissingLocalId => UserManagementError::InvalidUid,
+            AuthErrorCode::InsufficientPermission
+            | AuthErrorCode::ProjectNotFound
+            | AuthErrorCode::Unauthenticated => UserManagementError::InsufficientPermission,
+            _ => UserManagementError::Unknown,
+        }
+    }
+
     pub fn refresh_token_error(&self) -> RefreshTokenError {
-        match self.message.as_str() {
-            "TOKEN_EXPIRED" => RefreshTokenError::InvalidGrantType,
-            "USER_DISABLED" => RefreshTokenError::UserDisabled,
-            "USER_NOT_FOUND" => RefreshTokenError::UserNotFound,
-            "INVALID_REFRESH_TOKEN" => RefreshTokenError::InvalidRefreshToken,
-            "INVALID_GRANT_TYPE" => RefreshTokenError::InvalidGrantType,
+        match self.auth_code() {
+            AuthErrorCode::TokenExpired => RefreshTokenError::TokenExpired,
+            AuthErrorCode::UserDisabled => RefreshTokenError::UserDisabled,
+            AuthErrorCode::UserNotFound => RefreshTokenError::UserNotFound,
+            AuthErrorCode::InvalidRefreshToken | AuthErrorCode::MissingRefreshToken => {
+                RefreshTokenError::InvalidRefreshToken
+            }
+            AuthErrorCode::InvalidGrantType => RefreshTokenError::InvalidGrantType,
             _ => RefreshTokenError::Unknown,
         }
     }
@@ -1,35 +1,177 @@
+pub mod action_code;
+#[cfg(feature = "actix")]
+pub mod actix;
+pub mod admin;
 pub mod error;
 mod model;
 pub mod oauth;
+pub mod phone;
+pub mod session;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod verify;
 
-use error::{AccountError, RefreshTokenError};
-pub use model::RegisterResponse;
-use std::sync::Arc;
+use action_code::{ActionCodeSettings, AppliedActionCode};
+use admin::{credentials::AdminCredentials, ServiceAccount};
+use error::{
+    AccountError, ActionCodeError, CustomTokenError, EmailLinkError, LinkError, MfaError,
+    PasswordResetError, PhoneAuthError, RefreshTokenError, SessionCookieError, SignInMethodsError,
+    UpdateCredentialError, UserManagementError,
+};
+pub use model::{
+    AnonymousSignInResponse, CustomTokenSignInResponse, EmailLinkSignInResponse, LinkResponse,
+    MfaChallenge, MfaEnrollment, MfaEnrollmentRequest, MfaEnrollmentResponse, MfaEnrollmentSession,
+    MfaEnrollmentVerification, MfaSignInResponse, MfaVerification, PhoneSignInResponse,
+    ProfileUpdate, ProviderUserInfo, RegisterResponse, SignInMethod, SignInMethods, TotpSecret,
+    UnlinkResponse, UpdateAccountResponse, UserRecord,
+};
+use serde::{de::DeserializeOwned, de::IgnoredAny, Serialize};
+use session::{Session, SessionTokens};
+use std::{
+    env,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use crate::{
-    error::{ErrorContainer, LoginError, RegisterError},
-    model::{LoginBody, LoginResponse},
+    error::{AdminErrorKind, ErrorContainer, ErrorKind, FirebaseError, LoginError, RegisterError},
+    model::{
+        AdminLookupBody, CreateSessionCookieBody, CreateSessionCookieResponse, LoginBody,
+        LoginResponse, RevokeTokensBody,
+    },
     oauth::model::SignInWithIdpBody,
 };
-use awc::{http::StatusCode, Client};
-use model::{FirebaseRequest, RefreshedIdToken};
-use oauth::model::{OAuthToken, SignInWithIdpResponse};
+use awc::{http::StatusCode, Client, ClientRequest, SendClientRequest};
+use model::{
+    AccountLookupResponse, AnonymousSignUpBody, ApplyActionCodeBody, CreateAuthUriBody,
+    CustomTokenBody, EmailLinkSignInBody, FirebaseRequest, IdTokenBody, LinkEmailPasswordBody,
+    MfaEnrollmentStartBody, MfaEnrollmentStartResponse, MfaSignInStartBody, MfaStartResponse,
+    MfaWithdrawBody, PhoneSignInBody, PhoneSignInInfo, RefreshedIdToken, ResetPasswordBody,
+    ResetPasswordResponse, SendOobCodeBody, SendVerificationCodeBody, SendVerificationCodeResponse,
+    SignInOutcome, TotpEnrollmentInfo, UnlinkProvidersBody, UpdateCredentialBody,
+};
+use oauth::{
+    model::{OAuthToken, SignInWithIdpResponse},
+    Provider,
+};
+use verify::{
+    CachedKeySource, IdTokenClaims, KeySource, VerifyError, VerifyOptions, X509KeySource,
+    ID_TOKEN_CERTS_URL, SESSION_COOKIE_CERTS_URL,
+};
 
 #[derive(Clone)]
 pub struct Firebase {
     base_url: String,
+    v2_base_url: String,
     auth_token: String,
     client: Arc<Client>,
+    project_id: Option<String>,
+    id_token_keys: Rc<dyn KeySource>,
+    session_cookie_keys: Rc<dyn KeySource>,
+    admin_credentials: Option<Rc<AdminCredentials>>,
+    emulator_host: Option<String>,
+    clock_skew: Duration,
+}
+
+const EMULATOR_HOST_VAR: &str = "FIREBASE_AUTH_EMULATOR_HOST";
+const MIN_SESSION_COOKIE_DURATION: Duration = Duration::from_secs(5 * 60);
+const MAX_SESSION_COOKIE_DURATION: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+/// An unset or empty `FIREBASE_AUTH_EMULATOR_HOST` means the emulator is not used.
+fn emulator_host_from_var(value: Option<String>) -> Option<String> {
+    value.filter(|host| !host.is_empty())
 }
 
 impl Firebase {
+    /// Creates a client for the Identity Toolkit API at `base_url`.
+    ///
+    /// When `FIREBASE_AUTH_EMULATOR_HOST` is set, requests go to the emulator instead.
     pub fn auth(base_url: String, auth_token: String, client: Arc<Client>) -> Firebase {
+        let id_token_keys = Rc::new(CachedKeySource::new(X509KeySource::new(
+            client.clone(),
+            ID_TOKEN_CERTS_URL.to_owned(),
+        )));
+        let session_cookie_keys = Rc::new(CachedKeySource::new(X509KeySource::new(
+            client.clone(),
+            SESSION_COOKIE_CERTS_URL.to_owned(),
+        )));
         Firebase {
+            v2_base_url: match base_url.strip_suffix("/v1") {
+                Some(base_url) => format!("{}/v2", base_url),
+                None => format!("{}/v2", base_url.trim_end_matches('/')),
+            },
             base_url,
             auth_token,
             client,
+            project_id: None,
+            id_token_keys,
+            session_cookie_keys,
+            admin_credentials: None,
+            emulator_host: emulator_host_from_var(env::var(EMULATOR_HOST_VAR).ok()),
+            clock_skew: verify::DEFAULT_CLOCK_SKEW,
         }
     }
+
+    /// Sends every request to the Firebase Auth emulator at `host` (e.g. `localhost:9099`).
+    ///
+    /// The emulator issues unsigned tokens, so signatures are not checked in this mode.
+    pub fn with_emulator(mut self, host: String) -> Firebase {
+        self.emulator_host = Some(host);
+        self
+    }
+
+    /// Talks to the production endpoints, even if `FIREBASE_AUTH_EMULATOR_HOST` is set.
+    pub fn without_emulator(mut self) -> Firebase {
+        self.emulator_host = None;
+        self
+    }
+
+    pub fn is_emulator(&self) -> bool {
+        self.emulator_host.is_some()
+    }
+
+    /// Sets the URL of the v2 Identity Toolkit API used for multi-factor authentication.
+    ///
+    /// Defaults to `base_url` with its `/v1` suffix replaced by `/v2`, or `/v2` appended.
+    pub fn with_v2_base_url(mut self, v2_base_url: String) -> Firebase {
+        self.v2_base_url = v2_base_url;
+        self
+    }
+
+    /// Sets the project id that verified tokens must be issued for.
+    pub fn with_project_id(mut self, project_id: String) -> Firebase {
+        self.project_id = Some(project_id);
+        self
+    }
+
+    /// Sets how far the `exp`, `iat` and `auth_time` claims of verified tokens may be off
+    /// from the local clock, five minutes by default.
+    pub fn with_clock_skew(mut self, clock_skew: Duration) -> Firebase {
+        self.clock_skew = clock_skew;
+        self
+    }
+
+    /// Replaces the source of the public keys ID tokens are verified against.
+    pub fn with_id_token_key_source(mut self, key_source: Rc<dyn KeySource>) -> Firebase {
+        self.id_token_keys = key_source;
+        self
+    }
+
+    /// Replaces the source of the public keys session cookies are verified against.
+    pub fn with_session_cookie_key_source(mut self, key_source: Rc<dyn KeySource>) -> Firebase {
+        self.session_cookie_keys = key_source;
+        self
+    }
+
+    /// Authorizes admin requests with access tokens of `service_account`, whose project
+    /// is used unless a project id has been set.
+    pub fn with_service_account(mut self, service_account: ServiceAccount) -> Firebase {
+        self.project_id
+            .get_or_insert_with(|| service_account.project_id.clone());
+        self.admin_credentials = Some(Rc::new(AdminCredentials::new(service_account)));
+        self
+    }
 }
 
 impl Firebase {
@@ -37,25 +179,14 @@ impl Firebase {
         &self,
         email: String,
         password: String,
-    ) -> Result<LoginResponse, LoginError> {
+    ) -> Result<LoginResponse, FirebaseError<LoginError>> {
         let url = self.sign_in_url();
         let body = LoginBody::new(email, password);
-        let mut res = self
-            .client
-            .post(url)
-            .send_json(&body)
-            .await
-            .map_err(|_| LoginError::Unknown)?;
-
-        match res.status() {
-            StatusCode::OK => res
-                .json::<LoginResponse>()
-                .await
-                .map_err(|_| LoginError::Unknown),
-            _ => match res.json::<ErrorContainer>().await {
-                Ok(error) => Err(error.error.login_error()),
-                Err(_) => Err(LoginError::Unknown),
-            },
+        match self.post_json(url, &body).await? {
+            SignInOutcome::SignedIn(login) => Ok(login),
+            SignInOutcome::MfaRequired(challenge) => {
+                Err(FirebaseError::new(LoginError::MfaRequired(challenge)))
+            }
         }
     }
 }
@@ -65,110 +196,531 @@ impl Firebase {
         &self,
         email: String,
         password: String,
-    ) -> Result<RegisterResponse, RegisterError> {
+    ) -> Result<RegisterResponse, FirebaseError<RegisterError>> {
         let url = self.sign_up_url();
         let body = LoginBody::new(email, password);
-        let mut res = self
-            .client
-            .post(url)
-            .send_json(&body)
-            .await
-            .map_err(|_| RegisterError::Unknown)?;
-
-        match res.status() {
-            StatusCode::OK => res
-                .json::<RegisterResponse>()
-                .await
-                .map_err(|_| RegisterError::Unknown),
-            _ => match res.json::<ErrorContainer>().await {
-                Ok(error) => Err(error.error.register_error()),
-                Err(_) => Err(RegisterError::Unknown),
-            },
+        self.post_json(url, &body).await
+    }
+}
+
+impl Firebase {
+    /// Creates a new anonymous user, which can later be converted with `link_with_email_password`
+    /// or `link_with_idp` without changing its `local_id`.
+    pub async fn sign_in_anonymously(
+        &self,
+    ) -> Result<AnonymousSignInResponse, FirebaseError<RegisterError>> {
+        let url = self.sign_up_url();
+        let body = AnonymousSignUpBody {
+            return_secure_token: true,
+        };
+        self.post_json(url, &body).await
+    }
+}
+
+impl Firebase {
+    /// Exchanges a custom token minted by a trusted server for an ID and refresh token.
+    pub async fn sign_in_with_custom_token(
+        &self,
+        token: String,
+    ) -> Result<CustomTokenSignInResponse, FirebaseError<CustomTokenError>> {
+        let url = self.sign_in_custom_token_url();
+        let body = CustomTokenBody {
+            token,
+            return_secure_token: true,
+        };
+
+        self.post_json(url, &body).await
+    }
+}
+
+impl Firebase {
+    pub async fn sign_in_with_idp(
+        &self,
+        request_uri: &str,
+        token: &OAuthToken,
+    ) -> Result<SignInWithIdpResponse, FirebaseError<LoginError>> {
+        let url = self.sign_in_oauth_url();
+        let body = SignInWithIdpBody {
+            id_token: None,
+            request_uri,
+            post_body: token.to_string(),
+            return_secure_token: true,
+            return_idp_credential: true,
+        };
+
+        match self.post_json(url, &body).await? {
+            SignInOutcome::SignedIn(response) => Ok(response),
+            SignInOutcome::MfaRequired(challenge) => {
+                Err(FirebaseError::new(LoginError::MfaRequired(challenge)))
+            }
         }
     }
 }
 
 impl Firebase {
-    pub async fn sign_in_with_idp<'a>(
+    /// Attaches an email and password to the account of `id_token`, e.g. to upgrade an
+    /// anonymous user.
+    pub async fn link_with_email_password(
         &self,
-        request_uri: &'a str,
+        id_token: String,
+        email: String,
+        password: String,
+    ) -> Result<LinkResponse, FirebaseError<LinkError>> {
+        let url = self.update_account_url();
+        let body = LinkEmailPasswordBody {
+            id_token,
+            email,
+            password,
+            return_secure_token: true,
+        };
+
+        self.post_json(url, &body).await
+    }
+
+    /// Attaches an identity provider credential to the account of `id_token`.
+    pub async fn link_with_idp(
+        &self,
+        id_token: String,
+        request_uri: &str,
         token: &OAuthToken,
-    ) -> Result<SignInWithIdpResponse, LoginError> {
+    ) -> Result<SignInWithIdpResponse, FirebaseError<LinkError>> {
         let url = self.sign_in_oauth_url();
         let body = SignInWithIdpBody {
+            id_token: Some(id_token),
             request_uri,
             post_body: token.to_string(),
             return_secure_token: true,
             return_idp_credential: true,
         };
 
-        let mut response = self
-            .client
-            .post(url.as_str())
-            .send_json(&body)
+        self.post_json(url, &body).await
+    }
+
+    /// Detaches identity providers from the account of `id_token`.
+    pub async fn unlink_providers(
+        &self,
+        id_token: String,
+        providers: &[Provider],
+    ) -> Result<UnlinkResponse, FirebaseError<LinkError>> {
+        let url = self.update_account_url();
+        let body = UnlinkProvidersBody {
+            id_token,
+            delete_provider: providers.iter().map(Provider::provider_id).collect(),
+        };
+
+        self.post_json(url, &body).await
+    }
+}
+
+impl Firebase {
+    /// Texts a verification code to `phone_number` and returns the `session_info` to pass to
+    /// `sign_in_with_phone_number` together with that code.
+    pub async fn send_phone_verification_code(
+        &self,
+        phone_number: String,
+        recaptcha_token: String,
+    ) -> Result<String, FirebaseError<PhoneAuthError>> {
+        if !phone::is_valid_e164(&phone_number) {
+            return Err(FirebaseError::new(PhoneAuthError::InvalidPhoneNumber));
+        }
+        let url = self.send_verification_code_url();
+        let body = SendVerificationCodeBody {
+            phone_number,
+            recaptcha_token,
+        };
+
+        self.post_json(url, &body)
             .await
-            .map_err(|_| LoginError::Unknown)?;
+            .map(|response: SendVerificationCodeResponse| response.session_info)
+    }
+
+    pub async fn sign_in_with_phone_number(
+        &self,
+        session_info: String,
+        code: String,
+    ) -> Result<PhoneSignInResponse, FirebaseError<PhoneAuthError>> {
+        self.phone_sign_in(PhoneSignInBody {
+            session_info,
+            code,
+            id_token: None,
+        })
+        .await
+    }
 
-        match response.status() {
-            StatusCode::OK => {
-                let body = response.json::<SignInWithIdpResponse>().await.unwrap();
-                Ok(body)
+    /// Attaches a verified phone number to the account of `id_token`.
+    pub async fn link_with_phone_number(
+        &self,
+        id_token: String,
+        session_info: String,
+        code: String,
+    ) -> Result<PhoneSignInResponse, FirebaseError<PhoneAuthError>> {
+        self.phone_sign_in(PhoneSignInBody {
+            session_info,
+            code,
+            id_token: Some(id_token),
+        })
+        .await
+    }
+
+    async fn phone_sign_in(
+        &self,
+        body: PhoneSignInBody,
+    ) -> Result<PhoneSignInResponse, FirebaseError<PhoneAuthError>> {
+        let url = self.sign_in_phone_number_url();
+
+        self.post_json(url, &body).await
+    }
+}
+
+impl Firebase {
+    /// Texts a code to the SMS second factor `mfa_enrollment_id`, chosen from the `mfa_info` of an
+    /// `MfaChallenge`, and returns the `session_info` for `MfaVerification::Phone`. TOTP factors
+    /// need no start.
+    pub async fn mfa_sign_in_start(
+        &self,
+        mfa_pending_credential: String,
+        mfa_enrollment_id: String,
+        recaptcha_token: String,
+    ) -> Result<String, FirebaseError<MfaError>> {
+        let url = self.mfa_sign_in_url("start");
+        let body = MfaSignInStartBody {
+            mfa_pending_credential,
+            mfa_enrollment_id,
+            phone_sign_in_info: PhoneSignInInfo { recaptcha_token },
+        };
+
+        self.post_json(url, &body)
+            .await
+            .map(|response: MfaStartResponse| response.phone_response_info.session_info)
+    }
+
+    /// Completes a sign-in that failed with `LoginError::MfaRequired`.
+    pub async fn mfa_sign_in_finalize(
+        &self,
+        mfa_pending_credential: String,
+        verification: MfaVerification,
+    ) -> Result<MfaSignInResponse, FirebaseError<MfaError>> {
+        let url = self.mfa_sign_in_url("finalize");
+        let body = verification.into_body(mfa_pending_credential);
+
+        self.post_json(url, &body).await
+    }
+}
+
+impl Firebase {
+    /// Starts enrolling a second factor for the signed in user, who needs a verified email.
+    pub async fn mfa_enrollment_start(
+        &self,
+        id_token: String,
+        request: MfaEnrollmentRequest,
+    ) -> Result<MfaEnrollmentSession, FirebaseError<MfaError>> {
+        let url = self.mfa_enrollment_url("start");
+        let body = match request {
+            MfaEnrollmentRequest::Phone {
+                phone_number,
+                recaptcha_token,
+            } => {
+                if !phone::is_valid_e164(&phone_number) {
+                    return Err(FirebaseError::new(MfaError::InvalidPhoneNumber));
+                }
+                MfaEnrollmentStartBody {
+                    id_token,
+                    phone_enrollment_info: Some(SendVerificationCodeBody {
+                        phone_number,
+                        recaptcha_token,
+                    }),
+                    totp_enrollment_info: None,
+                }
             }
-            _ => match response.json::<ErrorContainer>().await {
-                Ok(error) => Err(error.error.login_error()),
-                Err(_) => Err(LoginError::Unknown),
+            MfaEnrollmentRequest::Totp => MfaEnrollmentStartBody {
+                id_token,
+                phone_enrollment_info: None,
+                totp_enrollment_info: Some(TotpEnrollmentInfo {}),
             },
+        };
+
+        match self.post_json(url, &body).await? {
+            MfaEnrollmentStartResponse {
+                totp_session_info: Some(secret),
+                ..
+            } => Ok(MfaEnrollmentSession::Totp(secret)),
+            MfaEnrollmentStartResponse {
+                phone_session_info: Some(info),
+                ..
+            } => Ok(MfaEnrollmentSession::Phone {
+                session_info: info.session_info,
+            }),
+            _ => Err(FirebaseError::new(MfaError::Unknown)),
         }
     }
+
+    pub async fn mfa_enrollment_finalize(
+        &self,
+        id_token: String,
+        verification: MfaEnrollmentVerification,
+        display_name: Option<String>,
+    ) -> Result<MfaEnrollmentResponse, FirebaseError<MfaError>> {
+        let url = self.mfa_enrollment_url("finalize");
+        let body = verification.into_body(id_token, display_name);
+        self.mfa_enrollment_update(url, &body).await
+    }
+
+    /// Removes the second factor `mfa_enrollment_id` from the signed in user.
+    pub async fn mfa_withdraw(
+        &self,
+        id_token: String,
+        mfa_enrollment_id: String,
+    ) -> Result<MfaEnrollmentResponse, FirebaseError<MfaError>> {
+        let url = self.mfa_enrollment_url("withdraw");
+        let body = MfaWithdrawBody {
+            id_token,
+            mfa_enrollment_id,
+        };
+        self.mfa_enrollment_update(url, &body).await
+    }
+
+    async fn mfa_enrollment_update<T: serde::Serialize>(
+        &self,
+        url: String,
+        body: &T,
+    ) -> Result<MfaEnrollmentResponse, FirebaseError<MfaError>> {
+        self.post_json(url, body).await
+    }
 }
 
 impl Firebase {
-    pub async fn send_verification_email(&self, token: String) -> Result<(), AccountError> {
-        let url = self.send_verification_email_url();
-        let body = FirebaseRequest {
-            request_type: "VERIFY_EMAIL".to_owned(),
-            id_token: token,
+    pub async fn send_verification_email(
+        &self,
+        token: String,
+    ) -> Result<(), FirebaseError<AccountError>> {
+        let url = self.send_oob_code_url();
+        let body = SendOobCodeBody {
+            request_type: "VERIFY_EMAIL",
+            email: None,
+            id_token: Some(token),
+            new_email: None,
+            settings: None,
         };
 
-        let mut response = self
-            .client
-            .post(url)
-            .send_json(&body)
+        self.post_json(url, &body).await.map(|_: IgnoredAny| ())
+    }
+}
+
+impl Firebase {
+    /// Sends a link to `new_email`; the user's email is only changed once that link is followed
+    /// and its code passed to `apply_action_code`.
+    pub async fn send_verify_and_change_email(
+        &self,
+        id_token: String,
+        new_email: String,
+        settings: Option<&ActionCodeSettings>,
+    ) -> Result<(), FirebaseError<UpdateCredentialError>> {
+        let url = self.send_oob_code_url();
+        let body = SendOobCodeBody {
+            request_type: "VERIFY_AND_CHANGE_EMAIL",
+            email: None,
+            id_token: Some(id_token),
+            new_email: Some(new_email),
+            settings,
+        };
+
+        self.post_json(url, &body).await.map(|_: IgnoredAny| ())
+    }
+
+    /// Applies an email verification, email recovery or verify-and-change-email code.
+    pub async fn apply_action_code(
+        &self,
+        oob_code: String,
+    ) -> Result<AppliedActionCode, FirebaseError<ActionCodeError>> {
+        let url = self.update_account_url();
+        let body = ApplyActionCodeBody { oob_code };
+
+        self.post_json(url, &body).await
+    }
+}
+
+impl Firebase {
+    pub async fn send_password_reset_email(
+        &self,
+        email: String,
+    ) -> Result<(), FirebaseError<PasswordResetError>> {
+        let url = self.send_oob_code_url();
+        let body = SendOobCodeBody {
+            request_type: "PASSWORD_RESET",
+            email: Some(email),
+            id_token: None,
+            new_email: None,
+            settings: None,
+        };
+
+        self.post_json(url, &body).await.map(|_: IgnoredAny| ())
+    }
+
+    /// Checks a password reset code and returns the email of the account it applies to.
+    pub async fn verify_password_reset_code(
+        &self,
+        oob_code: String,
+    ) -> Result<String, FirebaseError<PasswordResetError>> {
+        self.reset_password(oob_code, None).await
+    }
+
+    pub async fn confirm_password_reset(
+        &self,
+        oob_code: String,
+        new_password: String,
+    ) -> Result<(), FirebaseError<PasswordResetError>> {
+        self.reset_password(oob_code, Some(new_password))
             .await
-            .map_err(|_| AccountError::Unknown)?;
+            .map(|_| ())
+    }
 
-        match response.status() {
-            StatusCode::OK => Ok(()),
-            _ => match response.json::<ErrorContainer>().await {
-                Ok(error) => Err(error.error.account_error()),
-                Err(_) => Err(AccountError::Unknown),
-            },
-        }
+    async fn reset_password(
+        &self,
+        oob_code: String,
+        new_password: Option<String>,
+    ) -> Result<String, FirebaseError<PasswordResetError>> {
+        let url = self.reset_password_url();
+        let body = ResetPasswordBody {
+            oob_code,
+            new_password,
+        };
+
+        self.post_json(url, &body)
+            .await
+            .map(|response: ResetPasswordResponse| response.email)
     }
 }
 
 impl Firebase {
-    pub async fn delete_account(&self, token: String) -> Result<(), AccountError> {
+    /// Emails a passwordless sign-in link, to be completed with `sign_in_with_email_link`.
+    pub async fn send_sign_in_link_to_email(
+        &self,
+        email: String,
+        settings: &ActionCodeSettings,
+    ) -> Result<(), FirebaseError<EmailLinkError>> {
+        let url = self.send_oob_code_url();
+        let body = SendOobCodeBody {
+            request_type: "EMAIL_SIGNIN",
+            email: Some(email),
+            id_token: None,
+            new_email: None,
+            settings: Some(settings),
+        };
+
+        self.post_json(url, &body).await.map(|_: IgnoredAny| ())
+    }
+
+    /// Signs in with the `oobCode` of an email link, see `ActionCodeLink::parse`.
+    pub async fn sign_in_with_email_link(
+        &self,
+        email: String,
+        oob_code: String,
+    ) -> Result<EmailLinkSignInResponse, FirebaseError<EmailLinkError>> {
+        let url = self.sign_in_email_link_url();
+        let body = EmailLinkSignInBody { email, oob_code };
+
+        self.post_json(url, &body).await
+    }
+}
+
+impl Firebase {
+    /// Lists the ways `email` can sign in. Projects with email enumeration protection always
+    /// answer with empty lists.
+    pub async fn fetch_sign_in_methods(
+        &self,
+        email: String,
+        continue_uri: String,
+    ) -> Result<SignInMethods, FirebaseError<SignInMethodsError>> {
+        let url = self.create_auth_uri_url();
+        let body = CreateAuthUriBody {
+            identifier: email,
+            continue_uri,
+        };
+
+        self.post_json(url, &body).await
+    }
+}
+
+impl Firebase {
+    /// Looks up the account the ID token belongs to.
+    pub async fn get_account_info(
+        &self,
+        id_token: String,
+    ) -> Result<UserRecord, FirebaseError<AccountError>> {
+        let url = self.lookup_account_url();
+        let body = IdTokenBody { id_token };
+
+        let lookup: AccountLookupResponse = self.post_json(url, &body).await?;
+        lookup
+            .users
+            .into_iter()
+            .next()
+            .ok_or_else(|| FirebaseError::new(AccountError::UserNotFound))
+    }
+}
+
+impl Firebase {
+    /// Sets or deletes the display name and photo URL of the signed in user.
+    pub async fn update_profile(
+        &self,
+        id_token: String,
+        update: ProfileUpdate,
+    ) -> Result<UpdateAccountResponse, FirebaseError<AccountError>> {
+        let url = self.update_account_url();
+        let body = update.into_body(id_token);
+
+        self.post_json(url, &body).await
+    }
+}
+
+impl Firebase {
+    pub async fn change_email(
+        &self,
+        id_token: String,
+        email: String,
+    ) -> Result<UpdateAccountResponse, FirebaseError<UpdateCredentialError>> {
+        self.update_credential(UpdateCredentialBody {
+            id_token,
+            email: Some(email),
+            password: None,
+            return_secure_token: true,
+        })
+        .await
+    }
+
+    pub async fn change_password(
+        &self,
+        id_token: String,
+        password: String,
+    ) -> Result<UpdateAccountResponse, FirebaseError<UpdateCredentialError>> {
+        self.update_credential(UpdateCredentialBody {
+            id_token,
+            email: None,
+            password: Some(password),
+            return_secure_token: true,
+        })
+        .await
+    }
+
+    async fn update_credential(
+        &self,
+        body: UpdateCredentialBody,
+    ) -> Result<UpdateAccountResponse, FirebaseError<UpdateCredentialError>> {
+        let url = self.update_account_url();
+
+        self.post_json(url, &body).await
+    }
+}
+
+impl Firebase {
+    pub async fn delete_account(&self, token: String) -> Result<(), FirebaseError<AccountError>> {
         let url = self.delete_account_url();
         let body = FirebaseRequest {
             request_type: "DELETE_ACCOUNT".to_owned(),
             id_token: token,
         };
 
-        let mut response = self
-            .client
-            .post(url)
-            .send_json(&body)
-            .await
-            .map_err(|_| AccountError::Unknown)?;
-
-        match response.status() {
-            StatusCode::OK => Ok(()),
-            _ => match response.json::<ErrorContainer>().await {
-                Ok(error) => Err(error.error.account_error()),
-                Err(_) => Err(AccountError::Unknown),
-            },
-        }
+        self.post_json(url, &body).await.map(|_: IgnoredAny| ())
     }
 }
 
@@ -176,27 +728,272 @@ impl Firebase {
     pub async fn exchange_refresh_token(
         &self,
         refresh_token: String,
-    ) -> Result<RefreshedIdToken, RefreshTokenError> {
+    ) -> Result<RefreshedIdToken, FirebaseError<RefreshTokenError>> {
         let url = self.exchange_refresh_token_url();
         let body = format!("grant_type=refresh_token&refresh_token={refresh_token}");
 
-        let mut response = self
+        let request = self
             .client
             .post(url)
             .insert_header(("Content-Type", "application/x-www-form-urlencoded"))
-            .send_body(body)
+            .send_body(body);
+        read_response(request).await
+    }
+
+    /// Starts a `Session` that keeps the ID token of a sign-in response fresh.
+    pub fn session<T: Into<SessionTokens>>(&self, tokens: T) -> Session {
+        Session::new(self.clone(), tokens)
+    }
+}
+
+impl Firebase {
+    /// Verifies the signature and claims of a Firebase ID token and returns its decoded claims.
+    pub async fn verify_id_token(&self, token: &str) -> Result<IdTokenClaims, VerifyError> {
+        self.verify_id_token_with(token, &VerifyOptions::default())
             .await
-            .map_err(|_| RefreshTokenError::Unknown)?;
-        match response.status() {
-            StatusCode::OK => match response.json::<RefreshedIdToken>().await {
-                Ok(token) => Ok(token),
-                Err(_) => Err(RefreshTokenError::Unknown),
-            },
-            _ => match response.json::<ErrorContainer>().await {
-                Ok(err) => Err(err.error.refresh_token_error()),
-                Err(_) => Err(RefreshTokenError::Unknown),
-            },
+    }
+
+    /// Like `verify_id_token`, with the additional checks of `options`.
+    pub async fn verify_id_token_with(
+        &self,
+        token: &str,
+        options: &VerifyOptions,
+    ) -> Result<IdTokenClaims, VerifyError> {
+        self.verify_with(
+            token,
+            verify::ID_TOKEN_ISSUER,
+            self.id_token_keys.as_ref(),
+            options,
+        )
+        .await
+    }
+
+    /// Verifies an ID token or session cookie, which only differ in issuer and keys.
+    async fn verify_with(
+        &self,
+        token: &str,
+        issuer_prefix: &str,
+        key_source: &dyn KeySource,
+        options: &VerifyOptions,
+    ) -> Result<IdTokenClaims, VerifyError> {
+        let project_id = self
+            .project_id
+            .as_deref()
+            .ok_or(VerifyError::MissingProjectId)?;
+
+        let claims = match self.emulator_host {
+            Some(_) => {
+                verify::verify_unsigned_token(token, project_id, issuer_prefix, self.clock_skew)?
+            }
+            None => {
+                verify::verify_token(
+                    token,
+                    project_id,
+                    issuer_prefix,
+                    key_source,
+                    self.clock_skew,
+                )
+                .await?
+            }
+        };
+        self.check_claims(claims, options).await
+    }
+
+    async fn check_claims(
+        &self,
+        claims: IdTokenClaims,
+        options: &VerifyOptions,
+    ) -> Result<IdTokenClaims, VerifyError> {
+        if !options.check_revoked {
+            return Ok(claims);
+        }
+
+        let user = self
+            .get_user(claims.uid())
+            .await
+            .map_err(|err| match err.kind() {
+                UserManagementError::UserNotFound => VerifyError::UserNotFound,
+                _ => VerifyError::UserLookup(err),
+            })?;
+        if user.disabled {
+            return Err(VerifyError::UserDisabled);
+        }
+        let valid_since = user
+            .valid_since
+            .and_then(|valid_since| valid_since.duration_since(UNIX_EPOCH).ok())
+            .map(|valid_since| valid_since.as_secs())
+            .unwrap_or_default();
+        if claims.auth_time < valid_since {
+            return Err(VerifyError::Revoked);
+        }
+        Ok(claims)
+    }
+}
+
+impl Firebase {
+    /// The project admin requests act on, failing before any request is sent when it or the
+    /// admin credentials are missing.
+    fn admin_project_id<K: AdminErrorKind>(&self) -> Result<&str, FirebaseError<K>> {
+        let project_id = self
+            .project_id
+            .as_deref()
+            .ok_or(FirebaseError::new(K::missing_project_id()))?;
+        if self.emulator_host.is_none() && self.admin_credentials.is_none() {
+            return Err(FirebaseError::new(K::missing_credentials()));
+        }
+        Ok(project_id)
+    }
+
+    /// Builds a POST request for an admin endpoint that acts on the whole project.
+    ///
+    /// Requests made against the emulator are authorized with its `Bearer owner` token, and
+    /// otherwise with an access token of the service account.
+    pub(crate) async fn admin_request<K: AdminErrorKind>(
+        &self,
+        url: &str,
+    ) -> Result<ClientRequest, FirebaseError<K>> {
+        let request = self.client.post(url);
+        let access_token = match (&self.emulator_host, &self.admin_credentials) {
+            (Some(_), _) => "owner".to_owned(),
+            (None, Some(credentials)) => credentials.access_token(&self.client).await?,
+            (None, None) => return Err(FirebaseError::new(K::missing_credentials())),
+        };
+        Ok(request.insert_header(("Authorization", format!("Bearer {}", access_token))))
+    }
+}
+
+impl Firebase {
+    /// Exchanges an ID token for a session cookie valid for `duration`, between 5 minutes
+    /// and 2 weeks. Requires admin credentials, see `with_service_account`.
+    pub async fn create_session_cookie(
+        &self,
+        id_token: String,
+        duration: Duration,
+    ) -> Result<String, FirebaseError<SessionCookieError>> {
+        if !(MIN_SESSION_COOKIE_DURATION..=MAX_SESSION_COOKIE_DURATION).contains(&duration) {
+            return Err(FirebaseError::new(SessionCookieError::InvalidDuration));
         }
+        let project_id = self.admin_project_id()?;
+
+        let url = self.create_session_cookie_url(project_id);
+        let body = CreateSessionCookieBody {
+            id_token,
+            valid_duration: duration.as_secs(),
+        };
+        let request = self.admin_request(&url).await?;
+        read_response(request.send_json(&body))
+            .await
+            .map(|response: CreateSessionCookieResponse| response.session_cookie)
+    }
+
+    /// Verifies the signature and claims of a session cookie and returns its decoded claims.
+    pub async fn verify_session_cookie(&self, cookie: &str) -> Result<IdTokenClaims, VerifyError> {
+        self.verify_session_cookie_with(cookie, &VerifyOptions::default())
+            .await
+    }
+
+    /// Like `verify_session_cookie`, with the additional checks of `options`.
+    pub async fn verify_session_cookie_with(
+        &self,
+        cookie: &str,
+        options: &VerifyOptions,
+    ) -> Result<IdTokenClaims, VerifyError> {
+        self.verify_with(
+            cookie,
+            verify::SESSION_COOKIE_ISSUER,
+            self.session_cookie_keys.as_ref(),
+            options,
+        )
+        .await
+    }
+}
+
+impl Firebase {
+    /// Looks up any user of the project. Requires admin credentials.
+    pub async fn get_user(
+        &self,
+        uid: &str,
+    ) -> Result<UserRecord, FirebaseError<UserManagementError>> {
+        check_uid(uid)?;
+        let project_id = self.admin_project_id()?;
+
+        let url = self.project_accounts_url(project_id, "lookup");
+        let body = AdminLookupBody {
+            local_id: vec![uid.to_owned()],
+        };
+        let request = self.admin_request(&url).await?;
+        let lookup: AccountLookupResponse = read_response(request.send_json(&body)).await?;
+        lookup
+            .users
+            .into_iter()
+            .next()
+            .ok_or_else(|| FirebaseError::new(UserManagementError::UserNotFound))
+    }
+
+    /// Revokes the refresh tokens of `uid`, so its sessions end once their ID token expires.
+    ///
+    /// ID tokens and session cookies issued until now are rejected right away when verified
+    /// with `VerifyOptions::check_revoked`. Requires admin credentials.
+    pub async fn revoke_refresh_tokens(
+        &self,
+        uid: &str,
+    ) -> Result<(), FirebaseError<UserManagementError>> {
+        check_uid(uid)?;
+        let project_id = self.admin_project_id()?;
+
+        let url = self.project_accounts_url(project_id, "update");
+        let body = RevokeTokensBody {
+            local_id: uid.to_owned(),
+            valid_since: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+        };
+        let request = self.admin_request(&url).await?;
+        read_response(request.send_json(&body))
+            .await
+            .map(|_: IgnoredAny| ())
+    }
+}
+
+/// Rejects uids Firebase would not accept, the same way `create_custom_token` does.
+fn check_uid(uid: &str) -> Result<(), FirebaseError<UserManagementError>> {
+    if uid.is_empty() || uid.chars().count() > 128 {
+        return Err(FirebaseError::new(UserManagementError::InvalidUid));
+    }
+    Ok(())
+}
+
+impl Firebase {
+    /// Posts `body` as JSON to `url` and decodes the response.
+    async fn post_json<B, T, K>(&self, url: String, body: &B) -> Result<T, FirebaseError<K>>
+    where
+        B: Serialize,
+        T: DeserializeOwned,
+        K: ErrorKind,
+    {
+        read_response(self.client.post(url).send_json(body)).await
+    }
+}
+
+/// Decodes a successful response as `T`, and any other one as the error Firebase sent back.
+async fn read_response<T, K>(request: SendClientRequest) -> Result<T, FirebaseError<K>>
+where
+    T: DeserializeOwned,
+    K: ErrorKind,
+{
+    let mut response = request.await.map_err(FirebaseError::send)?;
+    let status = response.status();
+
+    match status {
+        StatusCode::OK => response
+            .json::<T>()
+            .await
+            .map_err(|err| FirebaseError::payload(status, err)),
+        _ => match response.json::<ErrorContainer>().await {
+            Ok(error) => Err(FirebaseError::from_response(status, error.error)),
+            Err(err) => Err(FirebaseError::payload(status, err)),
+        },
     }
 }
 
@@ -204,36 +1001,180 @@ impl Firebase {
     fn sign_in_oauth_url(&self) -> String {
         format!(
             "{}/accounts:signInWithIdp?key={}",
-            self.base_url, self.auth_token
+            self.identity_toolkit_url(),
+            self.auth_token
         )
     }
 
     fn sign_in_url(&self) -> String {
         format!(
             "{}/accounts:signInWithPassword?key={}",
-            self.base_url, self.auth_token
+            self.identity_toolkit_url(),
+            self.auth_token
+        )
+    }
+
+    fn sign_in_custom_token_url(&self) -> String {
+        format!(
+            "{}/accounts:signInWithCustomToken?key={}",
+            self.identity_toolkit_url(),
+            self.auth_token
+        )
+    }
+
+    fn sign_in_email_link_url(&self) -> String {
+        format!(
+            "{}/accounts:signInWithEmailLink?key={}",
+            self.identity_toolkit_url(),
+            self.auth_token
+        )
+    }
+
+    fn send_verification_code_url(&self) -> String {
+        format!(
+            "{}/accounts:sendVerificationCode?key={}",
+            self.identity_toolkit_url(),
+            self.auth_token
+        )
+    }
+
+    fn sign_in_phone_number_url(&self) -> String {
+        format!(
+            "{}/accounts:signInWithPhoneNumber?key={}",
+            self.identity_toolkit_url(),
+            self.auth_token
         )
     }
 
     fn sign_up_url(&self) -> String {
-        format!("{}/accounts:signUp?key={}", self.base_url, self.auth_token)
+        format!(
+            "{}/accounts:signUp?key={}",
+            self.identity_toolkit_url(),
+            self.auth_token
+        )
     }
 
-    fn send_verification_email_url(&self) -> String {
+    fn send_oob_code_url(&self) -> String {
         format!(
             "{}/accounts:sendOobCode?key={}",
-            self.base_url, self.auth_token
+            self.identity_toolkit_url(),
+            self.auth_token
+        )
+    }
+
+    fn reset_password_url(&self) -> String {
+        format!(
+            "{}/accounts:resetPassword?key={}",
+            self.identity_toolkit_url(),
+            self.auth_token
+        )
+    }
+
+    fn update_account_url(&self) -> String {
+        format!(
+            "{}/accounts:update?key={}",
+            self.identity_toolkit_url(),
+            self.auth_token
+        )
+    }
+
+    fn create_auth_uri_url(&self) -> String {
+        format!(
+            "{}/accounts:createAuthUri?key={}",
+            self.identity_toolkit_url(),
+            self.auth_token
+        )
+    }
+
+    fn lookup_account_url(&self) -> String {
+        format!(
+            "{}/accounts:lookup?key={}",
+            self.identity_toolkit_url(),
+            self.auth_token
         )
     }
 
     fn delete_account_url(&self) -> String {
-        format!("{}/accounts:delete?key={}", self.base_url, self.auth_token)
+        format!(
+            "{}/accounts:delete?key={}",
+            self.identity_toolkit_url(),
+            self.auth_token
+        )
     }
 
     fn exchange_refresh_token_url(&self) -> String {
+        format!("{}/token?key={}", self.secure_token_url(), self.auth_token)
+    }
+
+    fn mfa_sign_in_url(&self, step: &str) -> String {
+        format!(
+            "{}/accounts/mfaSignIn:{}?key={}",
+            self.identity_toolkit_v2_url(),
+            step,
+            self.auth_token
+        )
+    }
+
+    fn mfa_enrollment_url(&self, step: &str) -> String {
         format!(
-            "https://securetoken.googleapis.com/v1/token?key={}",
+            "{}/accounts/mfaEnrollment:{}?key={}",
+            self.identity_toolkit_v2_url(),
+            step,
             self.auth_token
         )
     }
+
+    fn create_session_cookie_url(&self, project_id: &str) -> String {
+        format!(
+            "{}/projects/{}:createSessionCookie",
+            self.identity_toolkit_url(),
+            project_id
+        )
+    }
+
+    fn project_accounts_url(&self, project_id: &str, method: &str) -> String {
+        format!(
+            "{}/projects/{}/accounts:{}",
+            self.identity_toolkit_url(),
+            project_id,
+            method
+        )
+    }
+
+    fn identity_toolkit_url(&self) -> String {
+        match &self.emulator_host {
+            Some(host) => format!("http://{}/identitytoolkit.googleapis.com/v1", host),
+            None => self.base_url.clone(),
+        }
+    }
+
+    /// Multi-factor endpoints only exist in the v2 API.
+    fn identity_toolkit_v2_url(&self) -> String {
+        match &self.emulator_host {
+            Some(host) => format!("http://{}/identitytoolkit.googleapis.com/v2", host),
+            None => self.v2_base_url.clone(),
+        }
+    }
+
+    fn secure_token_url(&self) -> String {
+        match &self.emulator_host {
+            Some(host) => format!("http://{}/securetoken.googleapis.com/v1", host),
+            None => "https://securetoken.googleapis.com/v1".to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::emulator_host_from_var;
+
+    #[test]
+    fn empty_emulator_host_var_is_ignored() {
+        assert_eq!(
+            emulator_host_from_var(Some("localhost:9099".to_owned())).as_deref(),
+            Some("localhost:9099")
+        );
+        assert_eq!(emulator_host_from_var(Some(String::new())), None);
+        assert_eq!(emulator_host_from_var(None), None);
+    }
 }
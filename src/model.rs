@@ -1,6 +1,11 @@
+use std::time::SystemTime;
+
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
-#[derive(Serialize, Deserialize)]
+use crate::{action_code::ActionCodeSettings, oauth::Provider};
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct LoginResponse {
     pub kind: String,
     #[serde(rename = "localId")]
@@ -31,6 +36,456 @@ pub struct RegisterResponse {
     pub expires_in: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnonymousSignInResponse {
+    pub kind: String,
+    pub local_id: String,
+    pub id_token: String,
+    pub refresh_token: String,
+    pub expires_in: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkResponse {
+    pub local_id: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    pub id_token: String,
+    pub refresh_token: String,
+    pub expires_in: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomTokenSignInResponse {
+    pub id_token: String,
+    pub refresh_token: String,
+    pub expires_in: String,
+    #[serde(default)]
+    pub is_new_user: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailLinkSignInResponse {
+    pub kind: String,
+    pub local_id: String,
+    pub email: String,
+    pub id_token: String,
+    pub refresh_token: String,
+    pub expires_in: String,
+    #[serde(default)]
+    pub is_new_user: bool,
+}
+
+/// A user account as returned by `accounts:lookup`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserRecord {
+    pub local_id: String,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub display_name: Option<String>,
+    pub photo_url: Option<String>,
+    pub phone_number: Option<String>,
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(default)]
+    pub provider_user_info: Vec<ProviderUserInfo>,
+    #[serde(default)]
+    pub mfa_info: Vec<MfaEnrollment>,
+    /// Developer claims set through the Admin SDK, sent by Firebase as a JSON encoded string.
+    #[serde(default, deserialize_with = "de::json_object")]
+    pub custom_attributes: Option<Map<String, Value>>,
+    pub tenant_id: Option<String>,
+    #[serde(default, deserialize_with = "de::millis")]
+    pub created_at: Option<SystemTime>,
+    #[serde(default, deserialize_with = "de::millis")]
+    pub last_login_at: Option<SystemTime>,
+    #[serde(default, deserialize_with = "de::rfc3339")]
+    pub last_refresh_at: Option<SystemTime>,
+    #[serde(default, deserialize_with = "de::millis")]
+    pub password_updated_at: Option<SystemTime>,
+    /// Tokens issued before this instant have been revoked.
+    #[serde(default, deserialize_with = "de::seconds")]
+    pub valid_since: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderUserInfo {
+    pub provider_id: String,
+    pub federated_id: Option<String>,
+    pub raw_id: Option<String>,
+    pub email: Option<String>,
+    pub display_name: Option<String>,
+    pub photo_url: Option<String>,
+    pub phone_number: Option<String>,
+    pub screen_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaEnrollment {
+    pub mfa_enrollment_id: String,
+    pub display_name: Option<String>,
+    /// Obfuscated phone number of an SMS second factor.
+    pub phone_info: Option<String>,
+    /// Present, but empty, for TOTP second factors.
+    pub totp_info: Option<Value>,
+    #[serde(
+        default,
+        deserialize_with = "de::rfc3339",
+        serialize_with = "ser::rfc3339"
+    )]
+    pub enrolled_at: Option<SystemTime>,
+}
+
+impl MfaEnrollment {
+    pub fn is_totp(&self) -> bool {
+        self.totp_info.is_some()
+    }
+}
+
+/// Returned by a sign-in instead of tokens when the user has enrolled a second factor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaChallenge {
+    pub mfa_pending_credential: String,
+    /// The second factors to choose from.
+    #[serde(default)]
+    pub mfa_info: Vec<MfaEnrollment>,
+    #[serde(default)]
+    pub local_id: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+/// The body of a successful sign-in response, which carries either tokens or an MFA challenge.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum SignInOutcome<T> {
+    MfaRequired(MfaChallenge),
+    SignedIn(T),
+}
+
+/// The second factor proving the identity of the user in `Firebase::mfa_sign_in_finalize`.
+#[derive(Debug, Clone)]
+pub enum MfaVerification {
+    /// An SMS code, sent by `Firebase::mfa_sign_in_start`.
+    Phone { session_info: String, code: String },
+    Totp {
+        mfa_enrollment_id: String,
+        code: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaSignInResponse {
+    pub id_token: String,
+    pub refresh_token: String,
+}
+
+/// The second factor to enroll with `Firebase::mfa_enrollment_start`.
+#[derive(Debug, Clone)]
+pub enum MfaEnrollmentRequest {
+    Phone {
+        phone_number: String,
+        recaptcha_token: String,
+    },
+    Totp,
+}
+
+#[derive(Debug, Clone)]
+pub enum MfaEnrollmentSession {
+    /// An SMS code was sent to the phone number being enrolled.
+    Phone { session_info: String },
+    /// The secret to load into an authenticator app.
+    Totp(TotpSecret),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpSecret {
+    pub shared_secret_key: String,
+    #[serde(default = "TotpSecret::default_code_length")]
+    pub verification_code_length: u32,
+    #[serde(default = "TotpSecret::default_algorithm")]
+    pub hashing_algorithm: String,
+    #[serde(default = "TotpSecret::default_period")]
+    pub period_sec: u32,
+    pub session_info: String,
+}
+
+impl TotpSecret {
+    fn default_code_length() -> u32 {
+        6
+    }
+
+    fn default_algorithm() -> String {
+        "SHA1".to_owned()
+    }
+
+    fn default_period() -> u32 {
+        30
+    }
+
+    /// A Key URI, e.g. for rendering as a QR code that authenticator apps can scan.
+    pub fn otpauth_uri(&self, account_name: &str, issuer: &str) -> String {
+        let encode = |value: &str| {
+            url::form_urlencoded::byte_serialize(value.as_bytes())
+                .collect::<String>()
+                .replace('+', "%20")
+        };
+        format!(
+            "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm={}&digits={}&period={}",
+            encode(issuer),
+            encode(account_name),
+            self.shared_secret_key,
+            encode(issuer),
+            self.hashing_algorithm,
+            self.verification_code_length,
+            self.period_sec
+        )
+    }
+}
+
+/// Proves possession of the factor started with `Firebase::mfa_enrollment_start`.
+#[derive(Debug, Clone)]
+pub enum MfaEnrollmentVerification {
+    Phone { session_info: String, code: String },
+    Totp { session_info: String, code: String },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaEnrollmentResponse {
+    pub id_token: String,
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct AccountLookupResponse {
+    #[serde(default)]
+    pub users: Vec<UserRecord>,
+}
+
+/// Firebase encodes timestamps inconsistently: epoch milliseconds or seconds as strings, or RFC 3339.
+mod de {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use serde::{de::Error, Deserialize, Deserializer};
+    use serde_json::{Map, Value};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Number {
+        Integer(u64),
+        Float(f64),
+        String(String),
+    }
+
+    fn epoch<'de, D: Deserializer<'de>>(
+        deserializer: D,
+        millis_per_unit: u64,
+    ) -> Result<Option<SystemTime>, D::Error> {
+        let value = match Option::<Number>::deserialize(deserializer)? {
+            Some(Number::Integer(value)) => value,
+            Some(Number::Float(value)) => value as u64,
+            Some(Number::String(value)) => value.parse().map_err(D::Error::custom)?,
+            None => return Ok(None),
+        };
+        Ok(value
+            .checked_mul(millis_per_unit)
+            .and_then(|millis| UNIX_EPOCH.checked_add(Duration::from_millis(millis))))
+    }
+
+    pub fn millis<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<SystemTime>, D::Error> {
+        epoch(deserializer, 1)
+    }
+
+    pub fn seconds<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<SystemTime>, D::Error> {
+        epoch(deserializer, 1000)
+    }
+
+    pub fn rfc3339<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<SystemTime>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(value) => humantime::parse_rfc3339_weak(&value)
+                .map(Some)
+                .map_err(D::Error::custom),
+            None => Ok(None),
+        }
+    }
+
+    pub fn json_object<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Map<String, Value>>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(value) => serde_json::from_str(&value)
+                .map(Some)
+                .map_err(D::Error::custom),
+            None => Ok(None),
+        }
+    }
+}
+
+mod ser {
+    use std::time::SystemTime;
+
+    use serde::Serializer;
+
+    pub fn rfc3339<S: Serializer>(
+        time: &Option<SystemTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match time {
+            Some(time) => serializer.collect_str(&humantime::format_rfc3339(*time)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateAccountResponse {
+    pub local_id: String,
+    pub email: Option<String>,
+    pub display_name: Option<String>,
+    pub photo_url: Option<String>,
+    pub id_token: String,
+    pub refresh_token: String,
+    pub expires_in: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnlinkResponse {
+    pub local_id: String,
+    /// The providers still linked to the account.
+    #[serde(default)]
+    pub provider_user_info: Vec<ProviderUserInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhoneSignInResponse {
+    pub local_id: String,
+    pub phone_number: Option<String>,
+    pub id_token: String,
+    pub refresh_token: String,
+    pub expires_in: String,
+    #[serde(default)]
+    pub is_new_user: bool,
+}
+
+#[derive(Debug, Clone)]
+enum ProfileAttribute {
+    Set(String),
+    Delete,
+}
+
+/// Changes to a user's profile; attributes left untouched are not modified.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileUpdate {
+    display_name: Option<ProfileAttribute>,
+    photo_url: Option<ProfileAttribute>,
+}
+
+impl ProfileUpdate {
+    pub fn new() -> ProfileUpdate {
+        ProfileUpdate::default()
+    }
+
+    pub fn display_name(mut self, display_name: String) -> ProfileUpdate {
+        self.display_name = Some(ProfileAttribute::Set(display_name));
+        self
+    }
+
+    pub fn delete_display_name(mut self) -> ProfileUpdate {
+        self.display_name = Some(ProfileAttribute::Delete);
+        self
+    }
+
+    pub fn photo_url(mut self, photo_url: String) -> ProfileUpdate {
+        self.photo_url = Some(ProfileAttribute::Set(photo_url));
+        self
+    }
+
+    pub fn delete_photo_url(mut self) -> ProfileUpdate {
+        self.photo_url = Some(ProfileAttribute::Delete);
+        self
+    }
+
+    pub(crate) fn into_body(self, id_token: String) -> UpdateProfileBody {
+        let mut body = UpdateProfileBody {
+            id_token,
+            display_name: None,
+            photo_url: None,
+            delete_attribute: Vec::new(),
+            return_secure_token: true,
+        };
+        match self.display_name {
+            Some(ProfileAttribute::Set(display_name)) => body.display_name = Some(display_name),
+            Some(ProfileAttribute::Delete) => body.delete_attribute.push("DISPLAY_NAME"),
+            None => {}
+        }
+        match self.photo_url {
+            Some(ProfileAttribute::Set(photo_url)) => body.photo_url = Some(photo_url),
+            Some(ProfileAttribute::Delete) => body.delete_attribute.push("PHOTO_URL"),
+            None => {}
+        }
+        body
+    }
+}
+
+/// A way of signing in, as listed by `accounts:createAuthUri`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignInMethod {
+    Password,
+    EmailLink,
+    Idp(Provider),
+    /// A provider this crate has no support for, e.g. `phone` or `github.com`.
+    Other(String),
+}
+
+impl<'de> Deserialize<'de> for SignInMethod {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let method = String::deserialize(deserializer)?;
+        Ok(match method.as_str() {
+            "password" => SignInMethod::Password,
+            "emailLink" => SignInMethod::EmailLink,
+            _ => match Provider::from_provider_id(&method) {
+                Some(provider) => SignInMethod::Idp(provider),
+                None => SignInMethod::Other(method),
+            },
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignInMethods {
+    #[serde(default)]
+    pub registered: bool,
+    #[serde(default)]
+    pub all_providers: Vec<SignInMethod>,
+    #[serde(default, rename = "signinMethods")]
+    pub signin_methods: Vec<SignInMethod>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct LoginBody {
     pub email: String,
@@ -49,6 +504,311 @@ impl LoginBody {
     }
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AnonymousSignUpBody {
+    pub return_secure_token: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LinkEmailPasswordBody {
+    pub id_token: String,
+    pub email: String,
+    pub password: String,
+    pub return_secure_token: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateProfileBody {
+    pub id_token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub photo_url: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub delete_attribute: Vec<&'static str>,
+    pub return_secure_token: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateCredentialBody {
+    pub id_token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    pub return_secure_token: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UnlinkProvidersBody {
+    pub id_token: String,
+    pub delete_provider: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SendVerificationCodeBody {
+    pub phone_number: String,
+    pub recaptcha_token: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SendVerificationCodeResponse {
+    pub session_info: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PhoneSignInBody {
+    pub session_info: String,
+    pub code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MfaSignInStartBody {
+    pub mfa_pending_credential: String,
+    pub mfa_enrollment_id: String,
+    pub phone_sign_in_info: PhoneSignInInfo,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PhoneSignInInfo {
+    pub recaptcha_token: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MfaStartResponse {
+    pub phone_response_info: PhoneResponseInfo,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PhoneResponseInfo {
+    pub session_info: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MfaSignInFinalizeBody {
+    pub mfa_pending_credential: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mfa_enrollment_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone_verification_info: Option<PhoneVerificationInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub totp_verification_info: Option<TotpVerificationInfo>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PhoneVerificationInfo {
+    pub session_info: String,
+    pub code: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TotpVerificationInfo {
+    pub verification_code: String,
+}
+
+impl MfaVerification {
+    pub(crate) fn into_body(self, mfa_pending_credential: String) -> MfaSignInFinalizeBody {
+        match self {
+            MfaVerification::Phone { session_info, code } => MfaSignInFinalizeBody {
+                mfa_pending_credential,
+                mfa_enrollment_id: None,
+                phone_verification_info: Some(PhoneVerificationInfo { session_info, code }),
+                totp_verification_info: None,
+            },
+            MfaVerification::Totp {
+                mfa_enrollment_id,
+                code,
+            } => MfaSignInFinalizeBody {
+                mfa_pending_credential,
+                mfa_enrollment_id: Some(mfa_enrollment_id),
+                phone_verification_info: None,
+                totp_verification_info: Some(TotpVerificationInfo {
+                    verification_code: code,
+                }),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MfaEnrollmentStartBody {
+    pub id_token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone_enrollment_info: Option<SendVerificationCodeBody>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub totp_enrollment_info: Option<TotpEnrollmentInfo>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct TotpEnrollmentInfo {}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MfaEnrollmentStartResponse {
+    pub phone_session_info: Option<PhoneResponseInfo>,
+    pub totp_session_info: Option<TotpSecret>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MfaEnrollmentFinalizeBody {
+    pub id_token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone_verification_info: Option<PhoneVerificationInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub totp_verification_info: Option<TotpEnrollmentVerificationInfo>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TotpEnrollmentVerificationInfo {
+    pub session_info: String,
+    pub verification_code: String,
+}
+
+impl MfaEnrollmentVerification {
+    pub(crate) fn into_body(
+        self,
+        id_token: String,
+        display_name: Option<String>,
+    ) -> MfaEnrollmentFinalizeBody {
+        let mut body = MfaEnrollmentFinalizeBody {
+            id_token,
+            display_name,
+            phone_verification_info: None,
+            totp_verification_info: None,
+        };
+        match self {
+            MfaEnrollmentVerification::Phone { session_info, code } => {
+                body.phone_verification_info = Some(PhoneVerificationInfo { session_info, code })
+            }
+            MfaEnrollmentVerification::Totp { session_info, code } => {
+                body.totp_verification_info = Some(TotpEnrollmentVerificationInfo {
+                    session_info,
+                    verification_code: code,
+                })
+            }
+        }
+        body
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MfaWithdrawBody {
+    pub id_token: String,
+    pub mfa_enrollment_id: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AdminLookupBody {
+    pub local_id: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RevokeTokensBody {
+    pub local_id: String,
+    /// Seconds since the epoch.
+    pub valid_since: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CreateSessionCookieBody {
+    pub id_token: String,
+    /// In seconds.
+    pub valid_duration: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CreateSessionCookieResponse {
+    pub session_cookie: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CustomTokenBody {
+    pub token: String,
+    pub return_secure_token: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SendOobCodeBody<'a> {
+    pub request_type: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_email: Option<String>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<&'a ActionCodeSettings>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EmailLinkSignInBody {
+    pub email: String,
+    pub oob_code: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CreateAuthUriBody {
+    pub identifier: String,
+    pub continue_uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ApplyActionCodeBody {
+    pub oob_code: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ResetPasswordBody {
+    pub oob_code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_password: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ResetPasswordResponse {
+    pub email: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct IdTokenBody {
+    pub id_token: String,
+}
+
 #[derive(Serialize)]
 pub(crate) struct FirebaseRequest {
     #[serde(rename = "requestType")]
@@ -57,7 +817,7 @@ pub(crate) struct FirebaseRequest {
     pub id_token: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Debug, Deserialize)]
 pub struct RefreshedIdToken {
     pub expires_in: String,
     pub token_type: String,
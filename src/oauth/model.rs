@@ -11,6 +11,8 @@ use super::{
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SignInWithIdpBody<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
     pub request_uri: &'a str,
     pub post_body: String,
     pub return_secure_token: bool,
@@ -24,10 +26,11 @@ pub struct SignInWithIdpResponse {
     pub full_name: Option<String>,
     pub email: String,
     pub local_id: String,
+    pub id_token: String,
     pub refresh_token: String,
 }
 
-#[derive(Debug, Serialize, Clone, Copy)]
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Provider {
     Facebook,
     Google,
@@ -46,16 +49,22 @@ impl<'de> Deserialize<'de> for Provider {
         D: serde::Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        match s.as_str() {
-            "facebook.com" => Ok(Provider::Facebook),
-            "google.com" => Ok(Provider::Google),
-            "apple.com" => Ok(Provider::Apple),
-            _ => Err(serde::de::Error::custom(format!("Unknown provider: {}", s))),
-        }
+        Provider::from_provider_id(&s)
+            .ok_or_else(|| serde::de::Error::custom(format!("Unknown provider: {}", s)))
     }
 }
 
 impl Provider {
+    /// Returns the provider with the given id (e.g. `google.com`), if it is supported.
+    pub fn from_provider_id(provider_id: &str) -> Option<Provider> {
+        match provider_id {
+            "facebook.com" => Some(Provider::Facebook),
+            "google.com" => Some(Provider::Google),
+            "apple.com" => Some(Provider::Apple),
+            _ => None,
+        }
+    }
+
     pub fn provider_id(&self) -> String {
         match self {
             Provider::Facebook => "facebook.com".to_string(),
@@ -0,0 +1,12 @@
+/// Whether `phone_number` is in E.164 format: a `+`, a country code and at most 15 digits in
+/// total, e.g. `+14155552671`.
+pub fn is_valid_e164(phone_number: &str) -> bool {
+    match phone_number.strip_prefix('+') {
+        Some(digits) => {
+            (2..=15).contains(&digits.len())
+                && !digits.starts_with('0')
+                && digits.bytes().all(|digit| digit.is_ascii_digit())
+        }
+        None => false,
+    }
+}
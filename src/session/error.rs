@@ -0,0 +1,16 @@
+use std::{fmt, io};
+
+#[derive(Debug)]
+pub enum SessionStoreError {
+    Io(io::Error),
+    DecodingError(serde_json::Error),
+}
+
+impl fmt::Display for SessionStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SessionStoreError::Io(err) => write!(f, "{}", err),
+            SessionStoreError::DecodingError(err) => write!(f, "{}", err),
+        }
+    }
+}
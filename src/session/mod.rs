@@ -0,0 +1,263 @@
+pub mod error;
+pub mod store;
+
+pub use error::SessionStoreError;
+pub use store::{JsonFileSessionStore, MemorySessionStore, SessionStore};
+
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use futures_util::future::{FutureExt, LocalBoxFuture, Shared};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{FirebaseError, RefreshTokenError},
+    model::{
+        AnonymousSignInResponse, CustomTokenSignInResponse, EmailLinkSignInResponse, LinkResponse,
+        LoginResponse, MfaEnrollmentResponse, MfaSignInResponse, PhoneSignInResponse,
+        RefreshedIdToken, RegisterResponse, UpdateAccountResponse,
+    },
+    oauth::model::SignInWithIdpResponse,
+    Firebase,
+};
+
+const DEFAULT_REFRESH_SKEW: Duration = Duration::from_secs(5 * 60);
+/// Firebase ID tokens are valid for one hour.
+const DEFAULT_ID_TOKEN_LIFETIME: Duration = Duration::from_secs(60 * 60);
+
+type RotationHook = Rc<dyn Fn(&SessionTokens)>;
+type SaveErrorHook = Rc<dyn Fn(&SessionStoreError)>;
+type Refresh = Shared<LocalBoxFuture<'static, Result<String, FirebaseError<RefreshTokenError>>>>;
+
+/// The ID and refresh token pair of a signed in user.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTokens {
+    pub id_token: String,
+    pub refresh_token: String,
+    pub expires_at: SystemTime,
+}
+
+impl SessionTokens {
+    /// Uses `expires_in` (seconds, as sent by Firebase) when available, falling back to the
+    /// `exp` claim of the ID token.
+    pub fn new(id_token: String, refresh_token: String, expires_in: Option<&str>) -> SessionTokens {
+        let expires_at = expires_in
+            .and_then(|expires_in| expires_in.parse().ok())
+            .map(|seconds| SystemTime::now() + Duration::from_secs(seconds))
+            .or_else(|| token_expiry(&id_token))
+            .unwrap_or_else(|| SystemTime::now() + DEFAULT_ID_TOKEN_LIFETIME);
+
+        SessionTokens {
+            id_token,
+            refresh_token,
+            expires_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ExpiryClaim {
+    exp: u64,
+}
+
+/// Reads the `exp` claim without verifying the token, which is only ever used as a hint.
+fn token_expiry(id_token: &str) -> Option<SystemTime> {
+    let payload = id_token.split('.').nth(1)?;
+    let payload = URL_SAFE_NO_PAD.decode(payload).ok()?;
+    let claim = serde_json::from_slice::<ExpiryClaim>(&payload).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(claim.exp))
+}
+
+macro_rules! session_tokens_from {
+    ($response:ty) => {
+        impl From<$response> for SessionTokens {
+            fn from(response: $response) -> SessionTokens {
+                SessionTokens::new(
+                    response.id_token,
+                    response.refresh_token,
+                    Some(&response.expires_in),
+                )
+            }
+        }
+    };
+    ($response:ty, without_expiry) => {
+        impl From<$response> for SessionTokens {
+            fn from(response: $response) -> SessionTokens {
+                SessionTokens::new(response.id_token, response.refresh_token, None)
+            }
+        }
+    };
+}
+
+session_tokens_from!(LoginResponse);
+session_tokens_from!(RegisterResponse);
+session_tokens_from!(AnonymousSignInResponse);
+session_tokens_from!(CustomTokenSignInResponse);
+session_tokens_from!(EmailLinkSignInResponse);
+session_tokens_from!(LinkResponse);
+session_tokens_from!(PhoneSignInResponse);
+session_tokens_from!(UpdateAccountResponse);
+session_tokens_from!(RefreshedIdToken);
+session_tokens_from!(SignInWithIdpResponse, without_expiry);
+session_tokens_from!(MfaSignInResponse, without_expiry);
+session_tokens_from!(MfaEnrollmentResponse, without_expiry);
+
+/// Keeps the ID token of a signed in user fresh.
+///
+/// The ID token is exchanged for a new one once it is within the refresh skew of its
+/// expiry, and concurrent callers share a single refresh request.
+///
+/// Clones share the tokens and the refresh in flight, but each keeps its own refresh skew,
+/// store and hooks. A refresh is saved to the store, and reported to the hooks, of the
+/// clone that started it, even when other clones await it too.
+#[derive(Clone)]
+pub struct Session {
+    firebase: Firebase,
+    state: Rc<RefCell<SessionState>>,
+    refresh_skew: Duration,
+    store: Option<Rc<dyn SessionStore>>,
+    rotation_hooks: Vec<RotationHook>,
+    save_error_hooks: Vec<SaveErrorHook>,
+}
+
+struct SessionState {
+    tokens: SessionTokens,
+    refresh: Option<Refresh>,
+}
+
+impl Session {
+    pub fn new<T: Into<SessionTokens>>(firebase: Firebase, tokens: T) -> Session {
+        Session {
+            firebase,
+            state: Rc::new(RefCell::new(SessionState {
+                tokens: tokens.into(),
+                refresh: None,
+            })),
+            refresh_skew: DEFAULT_REFRESH_SKEW,
+            store: None,
+            rotation_hooks: Vec::new(),
+            save_error_hooks: Vec::new(),
+        }
+    }
+
+    /// Resumes the session saved in `store`, if any, and keeps saving to it.
+    pub async fn restore(
+        firebase: Firebase,
+        store: Rc<dyn SessionStore>,
+    ) -> Result<Option<Session>, SessionStoreError> {
+        let session = store
+            .load()
+            .await?
+            .map(|tokens| Session::new(firebase, tokens).with_store(store));
+        Ok(session)
+    }
+
+    /// Sets how long before expiry the ID token is refreshed.
+    pub fn with_refresh_skew(mut self, refresh_skew: Duration) -> Session {
+        self.refresh_skew = refresh_skew;
+        self
+    }
+
+    /// Saves the tokens to `store` after every refresh. Call `save` to also save the
+    /// current ones.
+    pub fn with_store(mut self, store: Rc<dyn SessionStore>) -> Session {
+        self.store = Some(store);
+        self
+    }
+
+    /// Calls `hook` with the new tokens whenever a refresh returns a new refresh token.
+    pub fn on_refresh_token_rotated<F: Fn(&SessionTokens) + 'static>(mut self, hook: F) -> Session {
+        self.rotation_hooks.push(Rc::new(hook));
+        self
+    }
+
+    /// Calls `hook` when the tokens of a refresh could not be saved to the store. The
+    /// refresh itself still succeeds, with the new tokens kept in memory.
+    pub fn on_save_error<F: Fn(&SessionStoreError) + 'static>(mut self, hook: F) -> Session {
+        self.save_error_hooks.push(Rc::new(hook));
+        self
+    }
+
+    /// Saves the current tokens to the store, if there is one.
+    pub async fn save(&self) -> Result<(), SessionStoreError> {
+        match &self.store {
+            Some(store) => store.save(&self.tokens()).await,
+            None => Ok(()),
+        }
+    }
+
+    /// The current tokens, which may have expired.
+    pub fn tokens(&self) -> SessionTokens {
+        self.state.borrow().tokens.clone()
+    }
+
+    /// Returns an ID token that is valid for at least the refresh skew, refreshing it first
+    /// if necessary.
+    pub async fn get_valid_id_token(&self) -> Result<String, FirebaseError<RefreshTokenError>> {
+        let fresh = {
+            let tokens = &self.state.borrow().tokens;
+            match tokens.expires_at.duration_since(SystemTime::now()) {
+                Ok(remaining) if remaining > self.refresh_skew => Some(tokens.id_token.clone()),
+                _ => None,
+            }
+        };
+
+        match fresh {
+            Some(id_token) => Ok(id_token),
+            None => self.refresh().await,
+        }
+    }
+
+    /// Exchanges the refresh token for a new ID token, regardless of the current one's expiry.
+    pub async fn refresh(&self) -> Result<String, FirebaseError<RefreshTokenError>> {
+        self.start_refresh().await
+    }
+
+    fn start_refresh(&self) -> Refresh {
+        let mut state = self.state.borrow_mut();
+        if let Some(refresh) = &state.refresh {
+            return refresh.clone();
+        }
+
+        let firebase = self.firebase.clone();
+        let refresh_token = state.tokens.refresh_token.clone();
+        let shared_state = self.state.clone();
+        let store = self.store.clone();
+        let rotation_hooks = self.rotation_hooks.clone();
+        let save_error_hooks = self.save_error_hooks.clone();
+
+        let refresh = async move {
+            let result = firebase.exchange_refresh_token(refresh_token.clone()).await;
+            let tokens = {
+                let mut state = shared_state.borrow_mut();
+                state.refresh = None;
+                state.tokens = SessionTokens::from(result?);
+                state.tokens.clone()
+            };
+
+            if tokens.refresh_token != refresh_token {
+                for hook in &rotation_hooks {
+                    hook(&tokens);
+                }
+            }
+            // A failed save is not worth failing the refresh over, the next one saves again.
+            if let Some(store) = store {
+                if let Err(err) = store.save(&tokens).await {
+                    for hook in &save_error_hooks {
+                        hook(&err);
+                    }
+                }
+            }
+            Ok(tokens.id_token)
+        }
+        .boxed_local()
+        .shared();
+
+        state.refresh = Some(refresh.clone());
+        refresh
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    cell::RefCell,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::PathBuf,
+};
+
+use async_trait::async_trait;
+
+use super::{error::SessionStoreError, SessionTokens};
+
+/// Persists the tokens of a `Session`, so it survives restarts.
+///
+/// A session saves its tokens after every refresh; see `Session::with_store`.
+#[async_trait(?Send)]
+pub trait SessionStore {
+    /// Returns `None` when nothing has been saved yet.
+    async fn load(&self) -> Result<Option<SessionTokens>, SessionStoreError>;
+    async fn save(&self, tokens: &SessionTokens) -> Result<(), SessionStoreError>;
+    async fn clear(&self) -> Result<(), SessionStoreError>;
+}
+
+/// Keeps the tokens in memory only, e.g. to share them between sessions or in tests.
+#[derive(Default)]
+pub struct MemorySessionStore {
+    tokens: RefCell<Option<SessionTokens>>,
+}
+
+impl MemorySessionStore {
+    pub fn new() -> MemorySessionStore {
+        MemorySessionStore::default()
+    }
+}
+
+#[async_trait(?Send)]
+impl SessionStore for MemorySessionStore {
+    async fn load(&self) -> Result<Option<SessionTokens>, SessionStoreError> {
+        Ok(self.tokens.borrow().clone())
+    }
+
+    async fn save(&self, tokens: &SessionTokens) -> Result<(), SessionStoreError> {
+        *self.tokens.borrow_mut() = Some(tokens.clone());
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), SessionStoreError> {
+        self.tokens.borrow_mut().take();
+        Ok(())
+    }
+}
+
+/// Stores the tokens as JSON in a file.
+///
+/// On unix the file is only readable by the current user; elsewhere it gets the default
+/// permissions of its directory. Files are read and written with blocking `std::fs` calls,
+/// which hold up the executor for as long as the disk takes.
+pub struct JsonFileSessionStore {
+    path: PathBuf,
+}
+
+impl JsonFileSessionStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> JsonFileSessionStore {
+        JsonFileSessionStore { path: path.into() }
+    }
+
+    /// Writes to a temporary file first, so a crash never leaves a truncated file behind.
+    fn write(&self, json: &[u8]) -> Result<(), SessionStoreError> {
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+
+        // A leftover of an interrupted write is removed, and anything appearing in its place
+        // since, including a symlink, makes the write fail rather than keep its permissions.
+        let _ = fs::remove_file(&temporary);
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = options.open(&temporary).map_err(SessionStoreError::Io)?;
+        file.write_all(json)
+            .and_then(|_| file.sync_all())
+            .map_err(SessionStoreError::Io)?;
+        fs::rename(&temporary, &self.path).map_err(SessionStoreError::Io)
+    }
+}
+
+#[async_trait(?Send)]
+impl SessionStore for JsonFileSessionStore {
+    async fn load(&self) -> Result<Option<SessionTokens>, SessionStoreError> {
+        match fs::read(&self.path) {
+            Ok(json) => serde_json::from_slice(&json)
+                .map(Some)
+                .map_err(SessionStoreError::DecodingError),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(SessionStoreError::Io(err)),
+        }
+    }
+
+    async fn save(&self, tokens: &SessionTokens) -> Result<(), SessionStoreError> {
+        let json = serde_json::to_vec(tokens).map_err(SessionStoreError::DecodingError)?;
+        self.write(&json)
+    }
+
+    async fn clear(&self) -> Result<(), SessionStoreError> {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(SessionStoreError::Io(err)),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,1004 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use actix_web::{http::header::AUTHORIZATION, web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{
+    store::{
+        FakeMfaEnrollment, FakeMfaFactor, FakeOobCode, FakePhoneCode, FakeProviderInfo, FakeStore,
+        ID_TOKEN_LIFETIME,
+    },
+    FAKE_ACCESS_TOKEN, FAKE_TOTP_CODE,
+};
+
+type Store = web::Data<Mutex<FakeStore>>;
+
+const MIN_PASSWORD_LENGTH: usize = 6;
+const CUSTOM_TOKEN_AUDIENCE: &str =
+    "https://identitytoolkit.googleapis.com/google.identity.identitytoolkit.v1.IdentityToolkit";
+
+pub(crate) async fn accounts(
+    store: Store,
+    method: web::Path<String>,
+    body: web::Json<Value>,
+) -> HttpResponse {
+    let mut store = store.lock().unwrap();
+    let result = match method.as_str() {
+        "signUp" => sign_up(&mut store, &body),
+        "signInWithPassword" => sign_in_with_password(&mut store, &body),
+        "sendOobCode" => send_oob_code(&mut store, &body),
+        "delete" => delete(&mut store, &body),
+        "lookup" => lookup(&store, &body),
+        "createAuthUri" => create_auth_uri(&store, &body),
+        "update" => update(&mut store, &body),
+        "resetPassword" => reset_password(&mut store, &body),
+        "signInWithEmailLink" => sign_in_with_email_link(&mut store, &body),
+        "sendVerificationCode" => send_verification_code(&mut store, &body),
+        "signInWithPhoneNumber" => sign_in_with_phone_number(&mut store, &body),
+        "signInWithIdp" => sign_in_with_idp(&mut store, &body),
+        "signInWithCustomToken" => sign_in_with_custom_token(&mut store, &body),
+        _ => return HttpResponse::NotFound().finish(),
+    };
+
+    match result {
+        Ok(body) => HttpResponse::Ok().json(body),
+        Err(message) => error(message),
+    }
+}
+
+pub(crate) async fn mfa_sign_in(
+    store: Store,
+    method: web::Path<String>,
+    body: web::Json<Value>,
+) -> HttpResponse {
+    let mut store = store.lock().unwrap();
+    let result = match method.as_str() {
+        "start" => mfa_sign_in_start(&mut store, &body),
+        "finalize" => mfa_sign_in_finalize(&mut store, &body),
+        _ => return HttpResponse::NotFound().finish(),
+    };
+
+    match result {
+        Ok(body) => HttpResponse::Ok().json(body),
+        Err(message) => error(message),
+    }
+}
+
+pub(crate) async fn mfa_enrollment(
+    store: Store,
+    method: web::Path<String>,
+    body: web::Json<Value>,
+) -> HttpResponse {
+    let mut store = store.lock().unwrap();
+    let result = match method.as_str() {
+        "start" => mfa_enrollment_start(&mut store, &body),
+        "finalize" => mfa_enrollment_finalize(&mut store, &body),
+        "withdraw" => mfa_withdraw(&mut store, &body),
+        _ => return HttpResponse::NotFound().finish(),
+    };
+
+    match result {
+        Ok(body) => HttpResponse::Ok().json(body),
+        Err(message) => error(message),
+    }
+}
+
+/// Admin endpoints, authorized like the emulator with `Bearer owner` or with an access token
+/// from `oauth_token`.
+pub(crate) async fn projects(
+    request: HttpRequest,
+    store: Store,
+    path: web::Path<(String, String)>,
+    body: web::Json<Value>,
+) -> HttpResponse {
+    if !is_admin(&request) {
+        return unauthenticated();
+    }
+
+    let mut store = store.lock().unwrap();
+    let (project_id, method) = path.into_inner();
+    if project_id != store.project_id {
+        return error("PROJECT_NOT_FOUND");
+    }
+    let result = match method.as_str() {
+        "createSessionCookie" => create_session_cookie(&mut store, &body),
+        _ => return HttpResponse::NotFound().finish(),
+    };
+
+    match result {
+        Ok(body) => HttpResponse::Ok().json(body),
+        Err(message) => error(message),
+    }
+}
+
+/// Admin variants of the `accounts` endpoints, acting on any user of the project.
+pub(crate) async fn project_accounts(
+    request: HttpRequest,
+    store: Store,
+    path: web::Path<(String, String)>,
+    body: web::Json<Value>,
+) -> HttpResponse {
+    if !is_admin(&request) {
+        return unauthenticated();
+    }
+
+    let mut store = store.lock().unwrap();
+    let (project_id, method) = path.into_inner();
+    if project_id != store.project_id {
+        return error("PROJECT_NOT_FOUND");
+    }
+    let result = match method.as_str() {
+        "lookup" => admin_lookup(&store, &body),
+        "update" => admin_update(&mut store, &body),
+        _ => return HttpResponse::NotFound().finish(),
+    };
+
+    match result {
+        Ok(body) => HttpResponse::Ok().json(body),
+        Err(message) => error(message),
+    }
+}
+
+fn is_admin(request: &HttpRequest) -> bool {
+    let authorization = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    let authorized = [
+        "Bearer owner".to_owned(),
+        format!("Bearer {}", FAKE_ACCESS_TOKEN),
+    ];
+    authorized
+        .iter()
+        .any(|value| Some(value.as_str()) == authorization)
+}
+
+fn unauthenticated() -> HttpResponse {
+    HttpResponse::Unauthorized().json(json!({
+        "error": { "code": 401, "message": "UNAUTHENTICATED", "status": "UNAUTHENTICATED" }
+    }))
+}
+
+#[derive(Deserialize)]
+pub(crate) struct AssertionForm {
+    grant_type: String,
+    assertion: String,
+}
+
+/// Google's OAuth token endpoint, accepting any service account assertion.
+pub(crate) async fn oauth_token(form: web::Form<AssertionForm>) -> HttpResponse {
+    if form.grant_type != "urn:ietf:params:oauth:grant-type:jwt-bearer"
+        || form.assertion.split('.').count() != 3
+    {
+        return HttpResponse::BadRequest().json(json!({ "error": "invalid_grant" }));
+    }
+    HttpResponse::Ok().json(json!({
+        "access_token": FAKE_ACCESS_TOKEN,
+        "expires_in": 3599,
+        "token_type": "Bearer",
+    }))
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TokenForm {
+    grant_type: String,
+    refresh_token: String,
+}
+
+pub(crate) async fn token(store: Store, form: web::Form<TokenForm>) -> HttpResponse {
+    let mut store = store.lock().unwrap();
+    if form.grant_type != "refresh_token" {
+        return token_error("INVALID_GRANT_TYPE");
+    }
+    let (local_id, provider) = match store.refresh_tokens.get(&form.refresh_token) {
+        Some(entry) => entry.clone(),
+        None if store.revoked_refresh_tokens.contains(&form.refresh_token) => {
+            return token_error("TOKEN_EXPIRED")
+        }
+        None => return token_error("INVALID_REFRESH_TOKEN"),
+    };
+    match store.users.get(&local_id) {
+        Some(user) if user.disabled => return token_error("USER_DISABLED"),
+        Some(_) => {}
+        None => return token_error("USER_NOT_FOUND"),
+    }
+
+    store.token_exchanges += 1;
+    let (id_token, refresh_token) = match store.issue_tokens(&local_id, &provider) {
+        Ok(tokens) => tokens,
+        Err(message) => return token_error(message),
+    };
+    HttpResponse::Ok().json(json!({
+        "expires_in": ID_TOKEN_LIFETIME.to_string(),
+        "token_type": "Bearer",
+        "refresh_token": refresh_token,
+        "id_token": id_token,
+        "user_id": local_id,
+        "project_id": store.project_id,
+    }))
+}
+
+fn error(message: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(json!({
+        "error": {
+            "code": 400,
+            "message": message,
+            "errors": [{ "message": message, "domain": "global", "reason": "invalid" }],
+        }
+    }))
+}
+
+/// The Secure Token API reports errors without the `errors` array.
+fn token_error(message: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(json!({
+        "error": { "code": 400, "message": message, "status": "INVALID_ARGUMENT" }
+    }))
+}
+
+fn create_session_cookie(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let id_token = string(body, "idToken").ok_or("MISSING_ID_TOKEN")?;
+    let valid_duration = body
+        .get("validDuration")
+        .and_then(Value::as_u64)
+        .filter(|duration| (5 * 60..=14 * 24 * 60 * 60).contains(duration))
+        .ok_or("INVALID_SESSION_COOKIE_DURATION")?;
+
+    let session_cookie = store.session_cookie(id_token, valid_duration)?;
+    Ok(json!({ "sessionCookie": session_cookie }))
+}
+
+fn admin_lookup(store: &FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let local_ids = body
+        .get("localId")
+        .and_then(Value::as_array)
+        .ok_or("MISSING_LOCAL_ID")?;
+    let users: Vec<Value> = local_ids
+        .iter()
+        .filter_map(Value::as_str)
+        .filter_map(|local_id| store.user_info(local_id).ok())
+        .collect();
+
+    // Like Firebase, unknown users are left out rather than reported as an error.
+    Ok(json!({ "kind": "identitytoolkit#GetAccountInfoResponse", "users": users }))
+}
+
+fn admin_update(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let local_id = string(body, "localId").ok_or("MISSING_LOCAL_ID")?;
+    if !store.users.contains_key(local_id) {
+        return Err("USER_NOT_FOUND");
+    }
+    if let Some(valid_since) = body.get("validSince").and_then(Value::as_u64) {
+        store.revoke_tokens(local_id, valid_since);
+    }
+    if let Some(disabled) = body.get("disableUser").and_then(Value::as_bool) {
+        store.users.get_mut(local_id).unwrap().disabled = disabled;
+    }
+    Ok(json!({ "kind": "identitytoolkit#SetAccountInfoResponse", "localId": local_id }))
+}
+
+fn string<'a>(body: &'a Value, key: &str) -> Option<&'a str> {
+    body.get(key).and_then(Value::as_str)
+}
+
+fn sign_up(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let email = string(body, "email");
+    let password = string(body, "password");
+
+    let (email, password) = match (email, password) {
+        (None, None) => (None, None),
+        (Some(_), None) => return Err("MISSING_PASSWORD"),
+        (None, Some(_)) => return Err("MISSING_EMAIL"),
+        (Some(email), Some(password)) => {
+            if store.find_by_email(email).is_some() {
+                return Err("EMAIL_EXISTS");
+            }
+            if password.len() < MIN_PASSWORD_LENGTH {
+                return Err("WEAK_PASSWORD : Password should be at least 6 characters");
+            }
+            (Some(email.to_owned()), Some(password.to_owned()))
+        }
+    };
+
+    let provider = match email {
+        Some(_) => "password",
+        None => "anonymous",
+    };
+    let user = store.create_user(email, password);
+    let (id_token, refresh_token) = store.issue_tokens(&user.local_id, provider)?;
+    Ok(json!({
+        "kind": "identitytoolkit#SignupNewUserResponse",
+        "localId": user.local_id,
+        "email": user.email.unwrap_or_default(),
+        "idToken": id_token,
+        "refreshToken": refresh_token,
+        "expiresIn": ID_TOKEN_LIFETIME.to_string(),
+    }))
+}
+
+fn sign_in_with_password(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let email = string(body, "email").ok_or("MISSING_EMAIL")?;
+    let password = string(body, "password").ok_or("MISSING_PASSWORD")?;
+
+    let user = store.find_by_email(email).ok_or("EMAIL_NOT_FOUND")?.clone();
+    if user.password.as_deref() != Some(password) {
+        return Err("INVALID_PASSWORD");
+    }
+    if user.disabled {
+        return Err("USER_DISABLED");
+    }
+    if let Some(challenge) = store.mfa_challenge(&user.local_id, "password") {
+        return Ok(challenge);
+    }
+
+    let (id_token, refresh_token) = store.issue_tokens(&user.local_id, "password")?;
+    Ok(json!({
+        "kind": "identitytoolkit#VerifyPasswordResponse",
+        "localId": user.local_id,
+        "email": email,
+        "displayName": user.display_name.unwrap_or_default(),
+        "idToken": id_token,
+        "refreshToken": refresh_token,
+        "expiresIn": ID_TOKEN_LIFETIME.to_string(),
+        "registered": true,
+    }))
+}
+
+fn sign_in_with_custom_token(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let token = string(body, "token")
+        .filter(|token| !token.is_empty())
+        .ok_or("MISSING_CUSTOM_TOKEN")?;
+    let payload = FakeStore::decode_payload(token).ok_or("INVALID_CUSTOM_TOKEN")?;
+    if string(&payload, "aud") != Some(CUSTOM_TOKEN_AUDIENCE) {
+        return Err("INVALID_CUSTOM_TOKEN");
+    }
+    let uid = string(&payload, "uid")
+        .filter(|uid| !uid.is_empty() && uid.len() <= 128)
+        .ok_or("INVALID_CUSTOM_TOKEN")?
+        .to_owned();
+
+    let is_new_user = !store.users.contains_key(&uid);
+    if is_new_user {
+        store.create_user_with_id(uid.clone(), None, None);
+    }
+    let user = store.users.get_mut(&uid).ok_or("USER_NOT_FOUND")?;
+    if user.disabled {
+        return Err("USER_DISABLED");
+    }
+    if let Some(claims) = payload.get("claims").and_then(Value::as_object) {
+        user.custom_claims = claims.clone();
+    }
+
+    let (id_token, refresh_token) = store.issue_tokens(&uid, "custom")?;
+    Ok(json!({
+        "kind": "identitytoolkit#VerifyCustomTokenResponse",
+        "idToken": id_token,
+        "refreshToken": refresh_token,
+        "expiresIn": ID_TOKEN_LIFETIME.to_string(),
+        "isNewUser": is_new_user,
+    }))
+}
+
+fn send_oob_code(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let request_type = string(body, "requestType").ok_or("MISSING_REQ_TYPE")?;
+    let email = match request_type {
+        "VERIFY_EMAIL" => {
+            let id_token = string(body, "idToken").ok_or("INVALID_ID_TOKEN")?;
+            let local_id = store.verify_id_token(id_token)?;
+            store
+                .users
+                .get(&local_id)
+                .ok_or("USER_NOT_FOUND")?
+                .email
+                .clone()
+                .ok_or("MISSING_EMAIL")?
+        }
+        "PASSWORD_RESET" => {
+            let email = string(body, "email").ok_or("MISSING_EMAIL")?;
+            store.find_by_email(email).ok_or("EMAIL_NOT_FOUND")?;
+            email.to_owned()
+        }
+        "EMAIL_SIGNIN" => {
+            if string(body, "continueUrl").is_none() {
+                return Err("MISSING_CONTINUE_URI");
+            }
+            string(body, "email").ok_or("MISSING_EMAIL")?.to_owned()
+        }
+        "VERIFY_AND_CHANGE_EMAIL" => {
+            let id_token = string(body, "idToken").ok_or("INVALID_ID_TOKEN")?;
+            let local_id = store.verify_id_token(id_token)?;
+            let new_email = string(body, "newEmail").ok_or("MISSING_NEW_EMAIL")?;
+            if store.find_by_email(new_email).is_some() {
+                return Err("EMAIL_EXISTS");
+            }
+            store
+                .users
+                .get(&local_id)
+                .ok_or("USER_NOT_FOUND")?
+                .email
+                .clone()
+                .ok_or("MISSING_EMAIL")?
+        }
+        _ => return Err("INVALID_REQ_TYPE"),
+    };
+
+    let oob_code = store.next_id("oob");
+    store.oob_codes.push(FakeOobCode {
+        oob_code,
+        request_type: request_type.to_owned(),
+        email: email.clone(),
+        new_email: string(body, "newEmail").map(str::to_owned),
+        continue_url: string(body, "continueUrl").map(str::to_owned),
+    });
+    Ok(json!({
+        "kind": "identitytoolkit#GetOobConfirmationCodeResponse",
+        "email": email,
+    }))
+}
+
+fn reset_password(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let oob_code = string(body, "oobCode").ok_or("MISSING_OOB_CODE")?;
+    let position = store
+        .oob_codes
+        .iter()
+        .position(|code| code.oob_code == oob_code && code.request_type == "PASSWORD_RESET")
+        .ok_or("INVALID_OOB_CODE")?;
+    let email = store.oob_codes[position].email.clone();
+
+    if let Some(new_password) = string(body, "newPassword") {
+        if new_password.len() < MIN_PASSWORD_LENGTH {
+            return Err("WEAK_PASSWORD : Password should be at least 6 characters");
+        }
+        let local_id = store
+            .find_by_email(&email)
+            .map(|user| user.local_id.clone())
+            .ok_or("EMAIL_NOT_FOUND")?;
+        if let Some(user) = store.users.get_mut(&local_id) {
+            user.password = Some(new_password.to_owned());
+        }
+        store.oob_codes.remove(position);
+    }
+
+    Ok(json!({
+        "kind": "identitytoolkit#ResetPasswordResponse",
+        "email": email,
+        "requestType": "PASSWORD_RESET",
+    }))
+}
+
+fn sign_in_with_email_link(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let email = string(body, "email").ok_or("MISSING_EMAIL")?;
+    let oob_code = string(body, "oobCode").ok_or("MISSING_OOB_CODE")?;
+    let position = store
+        .oob_codes
+        .iter()
+        .position(|code| code.oob_code == oob_code && code.request_type == "EMAIL_SIGNIN")
+        .ok_or("INVALID_OOB_CODE")?;
+    if store.oob_codes[position].email != email {
+        return Err("INVALID_EMAIL");
+    }
+    store.oob_codes.remove(position);
+
+    let existing = store.find_by_email(email).map(|user| user.local_id.clone());
+    let is_new_user = existing.is_none();
+    let local_id = match existing {
+        Some(local_id) => local_id,
+        None => store.create_user(Some(email.to_owned()), None).local_id,
+    };
+    let user = store.users.get_mut(&local_id).ok_or("USER_NOT_FOUND")?;
+    if user.disabled {
+        return Err("USER_DISABLED");
+    }
+    user.email_verified = true;
+
+    let (id_token, refresh_token) = store.issue_tokens(&local_id, "password")?;
+    Ok(json!({
+        "kind": "identitytoolkit#EmailLinkSigninResponse",
+        "localId": local_id,
+        "email": email,
+        "idToken": id_token,
+        "refreshToken": refresh_token,
+        "expiresIn": ID_TOKEN_LIFETIME.to_string(),
+        "isNewUser": is_new_user,
+    }))
+}
+
+fn create_auth_uri(store: &FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let email = string(body, "identifier").ok_or("MISSING_IDENTIFIER")?;
+    if string(body, "continueUri").is_none() {
+        return Err("MISSING_CONTINUE_URI");
+    }
+
+    let mut methods = Vec::new();
+    if let Some(user) = store.find_by_email(email) {
+        if user.password.is_some() {
+            methods.push("password".to_owned());
+        }
+        methods.extend(
+            user.providers
+                .iter()
+                .map(|provider| provider.provider_id.clone()),
+        );
+    }
+    Ok(json!({
+        "kind": "identitytoolkit#CreateAuthUriResponse",
+        "registered": !methods.is_empty(),
+        "allProviders": methods,
+        "signinMethods": methods,
+        "sessionId": store.project_id,
+    }))
+}
+
+fn lookup(store: &FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let id_token = string(body, "idToken").ok_or("INVALID_ID_TOKEN")?;
+    let local_id = store.verify_id_token(id_token)?;
+
+    Ok(json!({
+        "kind": "identitytoolkit#GetAccountInfoResponse",
+        "users": [store.user_info(&local_id)?],
+    }))
+}
+
+fn send_verification_code(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let phone_number = string(body, "phoneNumber").ok_or("MISSING_PHONE_NUMBER")?;
+    if !crate::phone::is_valid_e164(phone_number) {
+        return Err("INVALID_PHONE_NUMBER : Invalid format.");
+    }
+    match string(body, "recaptchaToken") {
+        Some("") | None => return Err("MISSING_RECAPTCHA_TOKEN"),
+        Some(_) => {}
+    }
+
+    let session_info = store.next_id("session");
+    let code = format!("{:06}", store.phone_codes.len() + 123456);
+    store.phone_codes.push(FakePhoneCode {
+        session_info: session_info.clone(),
+        phone_number: phone_number.to_owned(),
+        code,
+    });
+    Ok(json!({ "sessionInfo": session_info }))
+}
+
+fn sign_in_with_phone_number(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let session_info = string(body, "sessionInfo").ok_or("MISSING_SESSION_INFO")?;
+    let code = string(body, "code").ok_or("MISSING_CODE")?;
+    let position = store
+        .phone_codes
+        .iter()
+        .position(|phone_code| phone_code.session_info == session_info)
+        .ok_or("INVALID_SESSION_INFO")?;
+    if store.phone_codes[position].code != code {
+        return Err("INVALID_CODE");
+    }
+    let phone_number = store.phone_codes.remove(position).phone_number;
+
+    let existing = store
+        .find_by_phone_number(&phone_number)
+        .map(|user| user.local_id.clone());
+    let (local_id, is_new_user) = match (string(body, "idToken"), existing) {
+        (Some(id_token), existing) => {
+            let local_id = store.verify_id_token(id_token)?;
+            if existing.is_some_and(|existing| existing != local_id) {
+                return Err("PHONE_NUMBER_EXISTS");
+            }
+            (local_id, false)
+        }
+        (None, Some(local_id)) => (local_id, false),
+        (None, None) => (store.create_user(None, None).local_id, true),
+    };
+    let user = store.users.get_mut(&local_id).ok_or("USER_NOT_FOUND")?;
+    if user.disabled {
+        return Err("USER_DISABLED");
+    }
+    user.phone_number = Some(phone_number.clone());
+
+    let (id_token, refresh_token) = store.issue_tokens(&local_id, "phone")?;
+    Ok(json!({
+        "localId": local_id,
+        "phoneNumber": phone_number,
+        "idToken": id_token,
+        "refreshToken": refresh_token,
+        "expiresIn": ID_TOKEN_LIFETIME.to_string(),
+        "isNewUser": is_new_user,
+    }))
+}
+
+fn mfa_sign_in_start(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let pending_credential =
+        string(body, "mfaPendingCredential").ok_or("MISSING_MFA_PENDING_CREDENTIAL")?;
+    let enrollment_id = string(body, "mfaEnrollmentId").ok_or("MISSING_MFA_ENROLLMENT_ID")?;
+    let (local_id, _) = store
+        .pending_credentials
+        .get(pending_credential)
+        .ok_or("INVALID_PENDING_TOKEN")?;
+    let enrollment = store
+        .users
+        .get(local_id)
+        .ok_or("USER_NOT_FOUND")?
+        .mfa_enrollments
+        .iter()
+        .find(|enrollment| enrollment.mfa_enrollment_id == enrollment_id)
+        .ok_or("MFA_ENROLLMENT_NOT_FOUND")?;
+    let phone_number = match &enrollment.factor {
+        FakeMfaFactor::Phone(phone_number) => phone_number.clone(),
+        FakeMfaFactor::Totp { .. } => return Err("MFA_ENROLLMENT_NOT_FOUND"),
+    };
+    if body["phoneSignInInfo"]["recaptchaToken"]
+        .as_str()
+        .is_none_or(str::is_empty)
+    {
+        return Err("MISSING_RECAPTCHA_TOKEN");
+    }
+
+    let session_info = store.next_id("session");
+    let code = format!("{:06}", store.phone_codes.len() + 123456);
+    store.phone_codes.push(FakePhoneCode {
+        session_info: session_info.clone(),
+        phone_number,
+        code,
+    });
+    Ok(json!({ "phoneResponseInfo": { "sessionInfo": session_info } }))
+}
+
+fn mfa_sign_in_finalize(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let pending_credential =
+        string(body, "mfaPendingCredential").ok_or("MISSING_MFA_PENDING_CREDENTIAL")?;
+    let (local_id, provider) = store
+        .pending_credentials
+        .get(pending_credential)
+        .cloned()
+        .ok_or("INVALID_PENDING_TOKEN")?;
+    let enrollments = &store
+        .users
+        .get(&local_id)
+        .ok_or("USER_NOT_FOUND")?
+        .mfa_enrollments;
+
+    if let Some(verification) = body.get("phoneVerificationInfo") {
+        let session_info = string(verification, "sessionInfo").ok_or("MISSING_SESSION_INFO")?;
+        let position = store
+            .phone_codes
+            .iter()
+            .position(|phone_code| phone_code.session_info == session_info)
+            .ok_or("INVALID_SESSION_INFO")?;
+        let phone_code = &store.phone_codes[position];
+        if !enrollments.iter().any(|enrollment| {
+            matches!(&enrollment.factor, FakeMfaFactor::Phone(phone_number) if *phone_number == phone_code.phone_number)
+        }) {
+            return Err("MFA_ENROLLMENT_NOT_FOUND");
+        }
+        if string(verification, "code") != Some(phone_code.code.as_str()) {
+            return Err("INVALID_CODE");
+        }
+        store.phone_codes.remove(position);
+    } else {
+        let enrollment_id = string(body, "mfaEnrollmentId").ok_or("MISSING_MFA_ENROLLMENT_ID")?;
+        let enrollment = enrollments
+            .iter()
+            .find(|enrollment| enrollment.mfa_enrollment_id == enrollment_id)
+            .ok_or("MFA_ENROLLMENT_NOT_FOUND")?;
+        if !matches!(enrollment.factor, FakeMfaFactor::Totp { .. }) {
+            return Err("MFA_ENROLLMENT_NOT_FOUND");
+        }
+        if body["totpVerificationInfo"]["verificationCode"].as_str() != Some(FAKE_TOTP_CODE) {
+            return Err("INVALID_VERIFICATION_CODE");
+        }
+    }
+
+    store.pending_credentials.remove(pending_credential);
+    let (id_token, refresh_token) = store.issue_tokens(&local_id, &provider)?;
+    Ok(json!({ "idToken": id_token, "refreshToken": refresh_token }))
+}
+
+const MAX_MFA_ENROLLMENTS: usize = 5;
+const FAKE_TOTP_SECRET: &str = "JBSWY3DPEHPK3PXP";
+
+fn mfa_enrollment_start(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let id_token = string(body, "idToken").ok_or("INVALID_ID_TOKEN")?;
+    let local_id = store.verify_id_token(id_token)?;
+    let user = store.users.get(&local_id).ok_or("USER_NOT_FOUND")?.clone();
+    if !user.email_verified {
+        return Err("UNVERIFIED_EMAIL");
+    }
+    if user.mfa_enrollments.len() >= MAX_MFA_ENROLLMENTS {
+        return Err("SECOND_FACTOR_LIMIT_EXCEEDED");
+    }
+
+    let session_info = store.next_id("session");
+    if let Some(info) = body.get("phoneEnrollmentInfo") {
+        let phone_number = string(info, "phoneNumber").ok_or("MISSING_PHONE_NUMBER")?;
+        if !crate::phone::is_valid_e164(phone_number) {
+            return Err("INVALID_PHONE_NUMBER : Invalid format.");
+        }
+        if user.mfa_enrollments.iter().any(|enrollment| {
+            matches!(&enrollment.factor, FakeMfaFactor::Phone(enrolled) if enrolled == phone_number)
+        }) {
+            return Err("SECOND_FACTOR_EXISTS");
+        }
+        let code = format!("{:06}", store.phone_codes.len() + 123456);
+        store.phone_codes.push(FakePhoneCode {
+            session_info: session_info.clone(),
+            phone_number: phone_number.to_owned(),
+            code,
+        });
+        Ok(json!({ "phoneSessionInfo": { "sessionInfo": session_info } }))
+    } else if body.get("totpEnrollmentInfo").is_some() {
+        store.totp_sessions.insert(
+            session_info.clone(),
+            (local_id, FAKE_TOTP_SECRET.to_owned()),
+        );
+        Ok(json!({
+            "totpSessionInfo": {
+                "sharedSecretKey": FAKE_TOTP_SECRET,
+                "verificationCodeLength": 6,
+                "hashingAlgorithm": "SHA1",
+                "periodSec": 30,
+                "sessionInfo": session_info,
+            }
+        }))
+    } else {
+        Err("INVALID_ARGUMENT")
+    }
+}
+
+fn mfa_enrollment_finalize(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let id_token = string(body, "idToken").ok_or("INVALID_ID_TOKEN")?;
+    let local_id = store.verify_id_token(id_token)?;
+
+    let factor = if let Some(verification) = body.get("phoneVerificationInfo") {
+        let session_info = string(verification, "sessionInfo").ok_or("MISSING_SESSION_INFO")?;
+        let position = store
+            .phone_codes
+            .iter()
+            .position(|phone_code| phone_code.session_info == session_info)
+            .ok_or("INVALID_SESSION_INFO")?;
+        if string(verification, "code") != Some(store.phone_codes[position].code.as_str()) {
+            return Err("INVALID_CODE");
+        }
+        FakeMfaFactor::Phone(store.phone_codes.remove(position).phone_number)
+    } else {
+        let verification = body.get("totpVerificationInfo").ok_or("INVALID_ARGUMENT")?;
+        let session_info = string(verification, "sessionInfo").ok_or("MISSING_SESSION_INFO")?;
+        match store.totp_sessions.get(session_info) {
+            Some((owner, _)) if *owner == local_id => {}
+            _ => return Err("INVALID_SESSION_INFO"),
+        }
+        if string(verification, "verificationCode") != Some(FAKE_TOTP_CODE) {
+            return Err("INVALID_VERIFICATION_CODE");
+        }
+        let (_, secret) = store.totp_sessions.remove(session_info).unwrap_or_default();
+        FakeMfaFactor::Totp { secret }
+    };
+
+    let mfa_enrollment_id = store.next_id("mfa");
+    let user = store.users.get_mut(&local_id).ok_or("USER_NOT_FOUND")?;
+    user.mfa_enrollments.push(FakeMfaEnrollment {
+        mfa_enrollment_id,
+        display_name: string(body, "displayName").map(str::to_owned),
+        factor,
+    });
+
+    let (id_token, refresh_token) = store.issue_tokens(&local_id, &sign_in_provider(id_token))?;
+    Ok(json!({ "idToken": id_token, "refreshToken": refresh_token }))
+}
+
+fn mfa_withdraw(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let id_token = string(body, "idToken").ok_or("INVALID_ID_TOKEN")?;
+    let local_id = store.verify_id_token(id_token)?;
+    let enrollment_id = string(body, "mfaEnrollmentId").ok_or("MISSING_MFA_ENROLLMENT_ID")?;
+
+    let user = store.users.get_mut(&local_id).ok_or("USER_NOT_FOUND")?;
+    let position = user
+        .mfa_enrollments
+        .iter()
+        .position(|enrollment| enrollment.mfa_enrollment_id == enrollment_id)
+        .ok_or("MFA_ENROLLMENT_NOT_FOUND")?;
+    user.mfa_enrollments.remove(position);
+
+    let (id_token, refresh_token) = store.issue_tokens(&local_id, &sign_in_provider(id_token))?;
+    Ok(json!({ "idToken": id_token, "refreshToken": refresh_token }))
+}
+
+/// The provider an (already verified) ID token was issued for.
+fn sign_in_provider(id_token: &str) -> String {
+    FakeStore::decode_payload(id_token)
+        .and_then(|claims| {
+            claims["firebase"]["sign_in_provider"]
+                .as_str()
+                .map(str::to_owned)
+        })
+        .unwrap_or_else(|| "password".to_owned())
+}
+
+fn delete(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let id_token = string(body, "idToken").ok_or("INVALID_ID_TOKEN")?;
+    let local_id = store.verify_id_token(id_token)?;
+
+    store.users.remove(&local_id);
+    store
+        .refresh_tokens
+        .retain(|_, (owner, _)| *owner != local_id);
+    Ok(json!({ "kind": "identitytoolkit#DeleteAccountResponse" }))
+}
+
+fn update(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    if let Some(oob_code) = string(body, "oobCode") {
+        return apply_oob_code(store, oob_code);
+    }
+    let id_token = string(body, "idToken").ok_or("INVALID_ID_TOKEN")?;
+    let local_id = store.verify_id_token(id_token)?;
+
+    if let Some(email) = string(body, "email") {
+        match store.find_by_email(email) {
+            Some(user) if user.local_id != local_id => return Err("EMAIL_EXISTS"),
+            _ => {}
+        }
+    }
+    if let Some(password) = string(body, "password") {
+        if password.len() < MIN_PASSWORD_LENGTH {
+            return Err("WEAK_PASSWORD : Password should be at least 6 characters");
+        }
+    }
+
+    let user = store.users.get_mut(&local_id).ok_or("USER_NOT_FOUND")?;
+    if let Some(email) = string(body, "email") {
+        user.email = Some(email.to_owned());
+    }
+    if let Some(password) = string(body, "password") {
+        user.password = Some(password.to_owned());
+    }
+    if let Some(display_name) = string(body, "displayName") {
+        user.display_name = Some(display_name.to_owned());
+    }
+    if let Some(photo_url) = string(body, "photoUrl") {
+        user.photo_url = Some(photo_url.to_owned());
+    }
+    for provider_id in body["deleteProvider"].as_array().into_iter().flatten() {
+        match provider_id.as_str() {
+            Some("password") => user.password = None,
+            Some(provider_id) => user
+                .providers
+                .retain(|provider| provider.provider_id != provider_id),
+            None => return Err("INVALID_PROVIDER_ID"),
+        }
+    }
+    for attribute in body["deleteAttribute"].as_array().into_iter().flatten() {
+        match attribute.as_str() {
+            Some("DISPLAY_NAME") => user.display_name = None,
+            Some("PHOTO_URL") => user.photo_url = None,
+            _ => return Err("INVALID_DELETE_ATTRIBUTE"),
+        }
+    }
+    let user = user.clone();
+
+    let (id_token, refresh_token) = store.issue_tokens(&local_id, "password")?;
+    Ok(json!({
+        "kind": "identitytoolkit#SetAccountInfoResponse",
+        "localId": local_id,
+        "email": user.email,
+        "displayName": user.display_name,
+        "photoUrl": user.photo_url,
+        "providerUserInfo": store.user_info(&local_id)?["providerUserInfo"],
+        "idToken": id_token,
+        "refreshToken": refresh_token,
+        "expiresIn": ID_TOKEN_LIFETIME.to_string(),
+    }))
+}
+
+fn apply_oob_code(store: &mut FakeStore, oob_code: &str) -> Result<Value, &'static str> {
+    let position = store
+        .oob_codes
+        .iter()
+        .position(|code| {
+            code.oob_code == oob_code
+                && matches!(
+                    code.request_type.as_str(),
+                    "VERIFY_EMAIL" | "VERIFY_AND_CHANGE_EMAIL"
+                )
+        })
+        .ok_or("INVALID_OOB_CODE")?;
+    let code = store.oob_codes.remove(position);
+
+    if let Some(new_email) = &code.new_email {
+        if store.find_by_email(new_email).is_some() {
+            return Err("EMAIL_EXISTS");
+        }
+    }
+    let user = store
+        .users
+        .values_mut()
+        .find(|user| user.email.as_deref() == Some(code.email.as_str()))
+        .ok_or("USER_NOT_FOUND")?;
+    if user.disabled {
+        return Err("USER_DISABLED");
+    }
+    if let Some(new_email) = &code.new_email {
+        user.email = Some(new_email.clone());
+    }
+    user.email_verified = true;
+
+    Ok(json!({
+        "kind": "identitytoolkit#SetAccountInfoResponse",
+        "email": code.email,
+        "newEmail": code.new_email,
+        "requestType": code.request_type,
+    }))
+}
+
+fn sign_in_with_idp(store: &mut FakeStore, body: &Value) -> Result<Value, &'static str> {
+    let post_body = string(body, "postBody").ok_or("INVALID_IDP_RESPONSE")?;
+    let params: HashMap<&str, &str> = post_body
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .collect();
+    let provider_id = *params.get("providerId").ok_or("INVALID_PROVIDER_ID")?;
+    let token = params
+        .get("id_token")
+        .or_else(|| params.get("access_token"))
+        .ok_or("INVALID_IDP_RESPONSE")?;
+
+    let account = store
+        .idp_accounts
+        .get(&(provider_id.to_owned(), token.to_string()))
+        .cloned()
+        .ok_or("INVALID_IDP_RESPONSE")?;
+
+    let linked = store
+        .find_by_federated_id(provider_id, &account.federated_id)
+        .map(|user| user.local_id.clone());
+    let local_id = match (string(body, "idToken"), linked) {
+        (Some(id_token), linked) => {
+            let local_id = store.verify_id_token(id_token)?;
+            match linked {
+                Some(linked) if linked != local_id => {
+                    return Err("FEDERATED_USER_ID_ALREADY_LINKED")
+                }
+                Some(_) => {}
+                None => {
+                    let user = store.users.get_mut(&local_id).ok_or("USER_NOT_FOUND")?;
+                    user.providers.push(FakeProviderInfo {
+                        provider_id: provider_id.to_owned(),
+                        federated_id: account.federated_id.clone(),
+                        email: Some(account.email.clone()),
+                    });
+                    if user.email.is_none() {
+                        user.email = Some(account.email.clone());
+                    }
+                }
+            }
+            local_id
+        }
+        (None, Some(linked)) => linked,
+        (None, None) => {
+            let mut user = store.create_user(Some(account.email.clone()), None);
+            user.display_name = account.full_name.clone();
+            user.email_verified = true;
+            user.providers.push(FakeProviderInfo {
+                provider_id: provider_id.to_owned(),
+                federated_id: account.federated_id.clone(),
+                email: Some(account.email.clone()),
+            });
+            store.users.insert(user.local_id.clone(), user.clone());
+            user.local_id
+        }
+    };
+    if store.users.get(&local_id).ok_or("USER_NOT_FOUND")?.disabled {
+        return Err("USER_DISABLED");
+    }
+    if let Some(challenge) = store.mfa_challenge(&local_id, provider_id) {
+        return Ok(challenge);
+    }
+
+    let (id_token, refresh_token) = store.issue_tokens(&local_id, provider_id)?;
+    Ok(json!({
+        "kind": "identitytoolkit#VerifyAssertionResponse",
+        "providerId": provider_id,
+        "federatedId": account.federated_id,
+        "fullName": account.full_name,
+        "email": account.email,
+        "localId": local_id,
+        "idToken": id_token,
+        "refreshToken": refresh_token,
+        "expiresIn": ID_TOKEN_LIFETIME.to_string(),
+    }))
+}
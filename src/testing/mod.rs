@@ -0,0 +1,167 @@
+//! An in-process stand-in for the Identity Toolkit and Secure Token APIs.
+//!
+//! `FakeServer` serves the same URL layout as the Firebase Auth emulator, so a
+//! `Firebase` client pointed at it with `with_emulator` runs unchanged.
+
+mod handlers;
+mod store;
+
+pub use store::{
+    FakeIdpAccount, FakeMfaEnrollment, FakeMfaFactor, FakeOobCode, FakePhoneCode, FakeProviderInfo,
+    FakeUser,
+};
+
+use std::{
+    io,
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, Mutex},
+};
+
+use actix_web::{dev::ServerHandle, web, App, HttpServer};
+use awc::Client;
+
+use crate::{oauth::Provider, Firebase};
+use store::FakeStore;
+
+pub const FAKE_PROJECT_ID: &str = "fake-project";
+/// The access token issued for any service account by the fake OAuth token endpoint.
+pub const FAKE_ACCESS_TOKEN: &str = "fake-access-token";
+/// The only code accepted for TOTP second factors.
+pub const FAKE_TOTP_CODE: &str = "123456";
+
+pub struct FakeServer {
+    addr: SocketAddr,
+    store: Arc<Mutex<FakeStore>>,
+    handle: ServerHandle,
+}
+
+impl FakeServer {
+    /// Starts the server on a random local port. Must be called from within an actix runtime.
+    pub fn start() -> io::Result<FakeServer> {
+        let store = Arc::new(Mutex::new(FakeStore::new(FAKE_PROJECT_ID.to_owned())));
+        let data = web::Data::from(store.clone());
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(data.clone())
+                .route(
+                    "/identitytoolkit.googleapis.com/v1/accounts:{method}",
+                    web::post().to(handlers::accounts),
+                )
+                .route(
+                    "/identitytoolkit.googleapis.com/v2/accounts/mfaSignIn:{method}",
+                    web::post().to(handlers::mfa_sign_in),
+                )
+                .route(
+                    "/identitytoolkit.googleapis.com/v2/accounts/mfaEnrollment:{method}",
+                    web::post().to(handlers::mfa_enrollment),
+                )
+                .route(
+                    "/identitytoolkit.googleapis.com/v1/projects/{project_id}:{method}",
+                    web::post().to(handlers::projects),
+                )
+                .route(
+                    "/identitytoolkit.googleapis.com/v1/projects/{project_id}/accounts:{method}",
+                    web::post().to(handlers::project_accounts),
+                )
+                .route(
+                    "/securetoken.googleapis.com/v1/token",
+                    web::post().to(handlers::token),
+                )
+                .route("/oauth2/token", web::post().to(handlers::oauth_token))
+        })
+        .workers(1)
+        .disable_signals()
+        .listen(listener)?
+        .run();
+
+        let handle = server.handle();
+        actix_rt::spawn(server);
+
+        Ok(FakeServer {
+            addr,
+            store,
+            handle,
+        })
+    }
+
+    /// The `host:port` the server listens on, usable as `FIREBASE_AUTH_EMULATOR_HOST`.
+    pub fn host(&self) -> String {
+        self.addr.to_string()
+    }
+
+    /// The URL of the OAuth token endpoint, to be used as a service account's `token_uri`.
+    pub fn token_uri(&self) -> String {
+        format!("http://{}/oauth2/token", self.host())
+    }
+
+    /// A `Firebase` client talking to this server.
+    pub fn firebase(&self, client: Arc<Client>) -> Firebase {
+        let base_url = format!("http://{}/identitytoolkit.googleapis.com/v1", self.host());
+        Firebase::auth(base_url, "fake-api-key".to_owned(), client)
+            .with_project_id(FAKE_PROJECT_ID.to_owned())
+            .with_emulator(self.host())
+    }
+
+    /// Registers an identity provider token accepted by `accounts:signInWithIdp`.
+    pub fn add_idp_account(&self, provider: Provider, token: &str, account: FakeIdpAccount) {
+        self.store
+            .lock()
+            .unwrap()
+            .idp_accounts
+            .insert((provider.provider_id(), token.to_owned()), account);
+    }
+
+    pub fn user(&self, local_id: &str) -> Option<FakeUser> {
+        self.store.lock().unwrap().users.get(local_id).cloned()
+    }
+
+    pub fn user_by_email(&self, email: &str) -> Option<FakeUser> {
+        self.store.lock().unwrap().find_by_email(email).cloned()
+    }
+
+    /// Enrolls a second factor, so signing in as the user requires it.
+    pub fn add_mfa_enrollment(&self, local_id: &str, enrollment: FakeMfaEnrollment) {
+        if let Some(user) = self.store.lock().unwrap().users.get_mut(local_id) {
+            user.mfa_enrollments.push(enrollment);
+        }
+    }
+
+    /// Rejects tokens issued before `valid_since` (in seconds) as revoked, like
+    /// `accounts:update` does, without revoking any refresh token.
+    pub fn set_valid_since(&self, local_id: &str, valid_since: u64) {
+        if let Some(user) = self.store.lock().unwrap().users.get_mut(local_id) {
+            user.valid_since = Some(valid_since);
+        }
+    }
+
+    pub fn set_disabled(&self, local_id: &str, disabled: bool) {
+        if let Some(user) = self.store.lock().unwrap().users.get_mut(local_id) {
+            user.disabled = disabled;
+        }
+    }
+
+    /// Out-of-band codes "emailed" by `accounts:sendOobCode`, oldest first.
+    pub fn oob_codes(&self) -> Vec<FakeOobCode> {
+        self.store.lock().unwrap().oob_codes.clone()
+    }
+
+    /// SMS codes "texted" by `accounts:sendVerificationCode`, oldest first.
+    pub fn phone_codes(&self) -> Vec<FakePhoneCode> {
+        self.store.lock().unwrap().phone_codes.clone()
+    }
+
+    /// How many times a refresh token has been exchanged for a new ID token.
+    pub fn token_exchanges(&self) -> usize {
+        self.store.lock().unwrap().token_exchanges
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        // The stop command is sent eagerly, there is no need to wait for completion.
+        drop(self.handle.stop(false));
+    }
+}
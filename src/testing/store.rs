@@ -0,0 +1,420 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde_json::{json, Value};
+
+use crate::verify::{self, IdTokenClaims};
+
+pub(crate) const ID_TOKEN_LIFETIME: u64 = 3600;
+
+#[derive(Debug, Clone)]
+pub struct FakeUser {
+    pub local_id: String,
+    pub email: Option<String>,
+    pub password: Option<String>,
+    pub phone_number: Option<String>,
+    pub display_name: Option<String>,
+    pub photo_url: Option<String>,
+    pub email_verified: bool,
+    pub disabled: bool,
+    pub providers: Vec<FakeProviderInfo>,
+    pub custom_claims: serde_json::Map<String, Value>,
+    pub mfa_enrollments: Vec<FakeMfaEnrollment>,
+    /// Milliseconds since the epoch.
+    pub created_at: u64,
+    pub last_login_at: Option<u64>,
+    /// Seconds since the epoch, tokens issued before have been revoked.
+    pub valid_since: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FakeMfaEnrollment {
+    pub mfa_enrollment_id: String,
+    pub display_name: Option<String>,
+    pub factor: FakeMfaFactor,
+}
+
+#[derive(Debug, Clone)]
+pub enum FakeMfaFactor {
+    Phone(String),
+    /// The fake does not implement RFC 6238, it accepts `FAKE_TOTP_CODE` for any secret.
+    Totp {
+        secret: String,
+    },
+}
+
+impl FakeMfaEnrollment {
+    pub(crate) fn to_json(&self) -> Value {
+        let mut info = json!({
+            "mfaEnrollmentId": self.mfa_enrollment_id,
+            "displayName": self.display_name,
+        });
+        match &self.factor {
+            FakeMfaFactor::Phone(phone_number) => info["phoneInfo"] = json!(phone_number),
+            FakeMfaFactor::Totp { .. } => info["totpInfo"] = json!({}),
+        }
+        info
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FakeProviderInfo {
+    pub provider_id: String,
+    pub federated_id: String,
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FakeIdpAccount {
+    pub federated_id: String,
+    pub email: String,
+    pub full_name: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FakeOobCode {
+    pub oob_code: String,
+    pub request_type: String,
+    pub email: String,
+    pub new_email: Option<String>,
+    pub continue_url: Option<String>,
+}
+
+impl FakeOobCode {
+    /// The action link Firebase would have put into the email.
+    pub fn link(&self) -> String {
+        let mode = match self.request_type.as_str() {
+            "EMAIL_SIGNIN" => "signIn",
+            "PASSWORD_RESET" => "resetPassword",
+            "VERIFY_EMAIL" => "verifyEmail",
+            "VERIFY_AND_CHANGE_EMAIL" => "verifyAndChangeEmail",
+            other => other,
+        };
+        let mut url = url::Url::parse("http://localhost/__/auth/action").unwrap();
+        url.query_pairs_mut()
+            .append_pair("apiKey", "fake-api-key")
+            .append_pair("mode", mode)
+            .append_pair("oobCode", &self.oob_code);
+        if let Some(continue_url) = &self.continue_url {
+            url.query_pairs_mut()
+                .append_pair("continueUrl", continue_url);
+        }
+        url.to_string()
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// An SMS verification code "texted" by `accounts:sendVerificationCode`.
+#[derive(Debug, Clone)]
+pub struct FakePhoneCode {
+    pub session_info: String,
+    pub phone_number: String,
+    pub code: String,
+}
+
+pub(crate) struct FakeStore {
+    pub project_id: String,
+    pub users: HashMap<String, FakeUser>,
+    /// Refresh token -> `(local_id, sign_in_provider)`.
+    pub refresh_tokens: HashMap<String, (String, String)>,
+    pub idp_accounts: HashMap<(String, String), FakeIdpAccount>,
+    pub oob_codes: Vec<FakeOobCode>,
+    pub phone_codes: Vec<FakePhoneCode>,
+    /// MFA pending credential -> `(local_id, sign_in_provider)`.
+    pub pending_credentials: HashMap<String, (String, String)>,
+    /// TOTP enrollment session info -> `(local_id, shared_secret_key)`.
+    pub totp_sessions: HashMap<String, (String, String)>,
+    /// Refresh tokens of users whose tokens have been revoked.
+    pub revoked_refresh_tokens: HashSet<String>,
+    /// Number of refresh tokens exchanged at the Secure Token API.
+    pub token_exchanges: usize,
+    next_id: u64,
+}
+
+impl FakeStore {
+    pub fn new(project_id: String) -> FakeStore {
+        FakeStore {
+            project_id,
+            users: HashMap::new(),
+            refresh_tokens: HashMap::new(),
+            idp_accounts: HashMap::new(),
+            oob_codes: Vec::new(),
+            phone_codes: Vec::new(),
+            pending_credentials: HashMap::new(),
+            totp_sessions: HashMap::new(),
+            revoked_refresh_tokens: HashSet::new(),
+            token_exchanges: 0,
+            next_id: 0,
+        }
+    }
+
+    pub fn next_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}-{}", prefix, self.next_id)
+    }
+
+    pub fn create_user(&mut self, email: Option<String>, password: Option<String>) -> FakeUser {
+        let local_id = self.next_id("user");
+        self.create_user_with_id(local_id, email, password)
+    }
+
+    pub fn create_user_with_id(
+        &mut self,
+        local_id: String,
+        email: Option<String>,
+        password: Option<String>,
+    ) -> FakeUser {
+        let user = FakeUser {
+            local_id,
+            email,
+            password,
+            phone_number: None,
+            display_name: None,
+            photo_url: None,
+            email_verified: false,
+            disabled: false,
+            providers: Vec::new(),
+            custom_claims: serde_json::Map::new(),
+            mfa_enrollments: Vec::new(),
+            created_at: now_millis(),
+            last_login_at: None,
+            valid_since: None,
+        };
+        self.users.insert(user.local_id.clone(), user.clone());
+        user
+    }
+
+    pub fn find_by_email(&self, email: &str) -> Option<&FakeUser> {
+        self.users
+            .values()
+            .find(|user| user.email.as_deref() == Some(email))
+    }
+
+    pub fn find_by_phone_number(&self, phone_number: &str) -> Option<&FakeUser> {
+        self.users
+            .values()
+            .find(|user| user.phone_number.as_deref() == Some(phone_number))
+    }
+
+    pub fn find_by_federated_id(&self, provider_id: &str, federated_id: &str) -> Option<&FakeUser> {
+        self.users.values().find(|user| {
+            user.providers.iter().any(|provider| {
+                provider.provider_id == provider_id && provider.federated_id == federated_id
+            })
+        })
+    }
+
+    /// Issues a new id/refresh token pair, returned as `(id_token, refresh_token)`.
+    pub fn issue_tokens(
+        &mut self,
+        local_id: &str,
+        sign_in_provider: &str,
+    ) -> Result<(String, String), &'static str> {
+        let user = self.users.get_mut(local_id).ok_or("USER_NOT_FOUND")?;
+        user.last_login_at = Some(now_millis());
+        let refresh_token = self.next_id("refresh");
+        self.refresh_tokens.insert(
+            refresh_token.clone(),
+            (local_id.to_owned(), sign_in_provider.to_owned()),
+        );
+        Ok((self.id_token(local_id, sign_in_provider)?, refresh_token))
+    }
+
+    /// Issues an MFA pending credential instead of tokens if the user has enrolled a second
+    /// factor, returned as the body of the challenge response.
+    pub fn mfa_challenge(&mut self, local_id: &str, sign_in_provider: &str) -> Option<Value> {
+        let user = self.users.get(local_id)?;
+        if user.mfa_enrollments.is_empty() {
+            return None;
+        }
+        let mfa_info: Vec<Value> = user
+            .mfa_enrollments
+            .iter()
+            .map(FakeMfaEnrollment::to_json)
+            .collect();
+        let email = user.email.clone();
+
+        let pending_credential = self.next_id("pending");
+        self.pending_credentials.insert(
+            pending_credential.clone(),
+            (local_id.to_owned(), sign_in_provider.to_owned()),
+        );
+        Some(json!({
+            "localId": local_id,
+            "email": email,
+            "mfaPendingCredential": pending_credential,
+            "mfaInfo": mfa_info,
+        }))
+    }
+
+    /// Encodes an unsigned ID token, in the same format as the Auth emulator.
+    fn id_token(&self, local_id: &str, sign_in_provider: &str) -> Result<String, &'static str> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let user = self.users.get(local_id).ok_or("USER_NOT_FOUND")?;
+
+        let mut identities = serde_json::Map::new();
+        if let Some(email) = &user.email {
+            identities.insert("email".to_owned(), json!([email]));
+        }
+        if let Some(phone_number) = &user.phone_number {
+            identities.insert("phone".to_owned(), json!([phone_number]));
+        }
+        for provider in &user.providers {
+            identities.insert(provider.provider_id.clone(), json!([provider.federated_id]));
+        }
+
+        let mut claims = json!({
+            "iss": format!("{}{}", verify::ID_TOKEN_ISSUER, self.project_id),
+            "aud": self.project_id,
+            "sub": local_id,
+            "user_id": local_id,
+            "iat": now,
+            "exp": now + ID_TOKEN_LIFETIME,
+            "auth_time": now,
+            "email": user.email,
+            "email_verified": user.email_verified,
+            "phone_number": user.phone_number,
+            "name": user.display_name,
+            "firebase": {
+                "identities": identities,
+                "sign_in_provider": sign_in_provider,
+            },
+        });
+        for (key, value) in &user.custom_claims {
+            claims[key] = value.clone();
+        }
+
+        let header = URL_SAFE_NO_PAD.encode(json!({ "alg": "none", "typ": "JWT" }).to_string());
+        let payload = URL_SAFE_NO_PAD.encode(claims.to_string());
+        Ok(format!("{}.{}.", header, payload))
+    }
+
+    /// Revokes the refresh tokens of the user and marks earlier ID tokens as revoked.
+    pub fn revoke_tokens(&mut self, local_id: &str, valid_since: u64) {
+        let revoked: Vec<String> = self
+            .refresh_tokens
+            .iter()
+            .filter(|(_, (owner, _))| owner == local_id)
+            .map(|(token, _)| token.clone())
+            .collect();
+        for token in revoked {
+            self.refresh_tokens.remove(&token);
+            self.revoked_refresh_tokens.insert(token);
+        }
+        if let Some(user) = self.users.get_mut(local_id) {
+            user.valid_since = Some(valid_since);
+        }
+    }
+
+    /// Issues a session cookie with the claims of `id_token`, valid for `valid_duration` seconds.
+    pub fn session_cookie(
+        &self,
+        id_token: &str,
+        valid_duration: u64,
+    ) -> Result<String, &'static str> {
+        self.verify_id_token(id_token)?;
+        let mut claims = FakeStore::decode_payload(id_token).ok_or("INVALID_ID_TOKEN")?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        claims["iss"] = json!(format!(
+            "{}{}",
+            verify::SESSION_COOKIE_ISSUER,
+            self.project_id
+        ));
+        claims["iat"] = json!(now);
+        claims["exp"] = json!(now + valid_duration);
+
+        let header = URL_SAFE_NO_PAD.encode(json!({ "alg": "none", "typ": "JWT" }).to_string());
+        let payload = URL_SAFE_NO_PAD.encode(claims.to_string());
+        Ok(format!("{}.{}.", header, payload))
+    }
+
+    /// Serializes a user the way `accounts:lookup` does.
+    pub fn user_info(&self, local_id: &str) -> Result<Value, &'static str> {
+        let user = self.users.get(local_id).ok_or("USER_NOT_FOUND")?;
+        let mut providers: Vec<Value> = user
+            .providers
+            .iter()
+            .map(|provider| {
+                json!({
+                    "providerId": provider.provider_id,
+                    "federatedId": provider.federated_id,
+                    "rawId": provider.federated_id,
+                    "email": provider.email,
+                })
+            })
+            .collect();
+        if let (Some(email), Some(_)) = (&user.email, &user.password) {
+            providers.push(json!({
+                "providerId": "password",
+                "federatedId": email,
+                "rawId": email,
+                "email": email,
+            }));
+        }
+        if let Some(phone_number) = &user.phone_number {
+            providers.push(json!({
+                "providerId": "phone",
+                "rawId": phone_number,
+                "phoneNumber": phone_number,
+            }));
+        }
+
+        let mut info = json!({
+            "localId": user.local_id,
+            "email": user.email,
+            "emailVerified": user.email_verified,
+            "displayName": user.display_name,
+            "photoUrl": user.photo_url,
+            "phoneNumber": user.phone_number,
+            "disabled": user.disabled,
+            "providerUserInfo": providers,
+            "mfaInfo": user.mfa_enrollments.iter().map(FakeMfaEnrollment::to_json).collect::<Vec<_>>(),
+            "createdAt": user.created_at.to_string(),
+            "lastLoginAt": user.last_login_at.map(|at| at.to_string()),
+            "validSince": user.valid_since.map(|at| at.to_string()),
+        });
+        if !user.custom_claims.is_empty() {
+            info["customAttributes"] =
+                Value::String(Value::Object(user.custom_claims.clone()).to_string());
+        }
+        Ok(info)
+    }
+
+    /// Decodes the payload of a JWT without checking its signature.
+    pub fn decode_payload(token: &str) -> Option<Value> {
+        let payload = token.split('.').nth(1)?;
+        let payload = URL_SAFE_NO_PAD.decode(payload).ok()?;
+        serde_json::from_slice(&payload).ok()
+    }
+
+    /// Returns the local id of the user an ID token was issued to.
+    pub fn verify_id_token(&self, id_token: &str) -> Result<String, &'static str> {
+        let claims: IdTokenClaims = verify::verify_unsigned_token(
+            id_token,
+            &self.project_id,
+            verify::ID_TOKEN_ISSUER,
+            verify::DEFAULT_CLOCK_SKEW,
+        )
+        .map_err(|_| "INVALID_ID_TOKEN")?;
+        match self.users.get(claims.uid()) {
+            Some(user) => Ok(user.local_id.clone()),
+            None => Err("USER_NOT_FOUND"),
+        }
+    }
+}
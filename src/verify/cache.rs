@@ -0,0 +1,143 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use futures_util::future::{FutureExt, LocalBoxFuture, Shared};
+
+use super::{
+    error::KeySourceError,
+    keys::{KeySet, KeySource},
+};
+
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_REFRESH_WINDOW: Duration = Duration::from_secs(5 * 60);
+const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+type Refresh = Shared<LocalBoxFuture<'static, Result<Arc<KeySet>, KeySourceError>>>;
+
+/// Caches the key set of another `KeySource` for as long as its `max_age` allows.
+///
+/// Keys are refreshed in the background once they enter the refresh window before
+/// expiry, concurrent refreshes share a single request and a failed refresh keeps
+/// serving the previous (stale) key set.
+pub struct CachedKeySource<S> {
+    source: Rc<S>,
+    state: Rc<RefCell<CacheState>>,
+    default_max_age: Duration,
+    refresh_window: Duration,
+    retry_interval: Duration,
+}
+
+struct CacheState {
+    keys: Option<Arc<KeySet>>,
+    refresh_at: Instant,
+    expires_at: Instant,
+    refresh: Option<Refresh>,
+}
+
+impl<S: KeySource + 'static> CachedKeySource<S> {
+    pub fn new(source: S) -> CachedKeySource<S> {
+        let now = Instant::now();
+        CachedKeySource {
+            source: Rc::new(source),
+            state: Rc::new(RefCell::new(CacheState {
+                keys: None,
+                refresh_at: now,
+                expires_at: now,
+                refresh: None,
+            })),
+            default_max_age: DEFAULT_MAX_AGE,
+            refresh_window: DEFAULT_REFRESH_WINDOW,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+        }
+    }
+
+    /// Sets how long keys are cached when the source does not report a `max_age`.
+    pub fn with_default_max_age(mut self, max_age: Duration) -> CachedKeySource<S> {
+        self.default_max_age = max_age;
+        self
+    }
+
+    /// Sets how long before expiry a background refresh is started.
+    pub fn with_refresh_window(mut self, refresh_window: Duration) -> CachedKeySource<S> {
+        self.refresh_window = refresh_window;
+        self
+    }
+
+    /// Sets how long to wait before retrying after a failed refresh.
+    pub fn with_retry_interval(mut self, retry_interval: Duration) -> CachedKeySource<S> {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    fn refresh(&self) -> Refresh {
+        let mut state = self.state.borrow_mut();
+        if let Some(refresh) = &state.refresh {
+            return refresh.clone();
+        }
+
+        let source = self.source.clone();
+        let shared_state = self.state.clone();
+        let default_max_age = self.default_max_age;
+        let refresh_window = self.refresh_window;
+        let retry_interval = self.retry_interval;
+
+        let refresh = async move {
+            let result = source.keys().await;
+            let now = Instant::now();
+            let mut state = shared_state.borrow_mut();
+            state.refresh = None;
+            match &result {
+                Ok(keys) => {
+                    let max_age = keys.max_age().unwrap_or(default_max_age);
+                    state.keys = Some(keys.clone());
+                    state.expires_at = now + max_age;
+                    state.refresh_at = now + max_age.saturating_sub(refresh_window);
+                }
+                Err(_) => state.refresh_at = now + retry_interval,
+            }
+            result
+        }
+        .boxed_local()
+        .shared();
+
+        state.refresh = Some(refresh.clone());
+        refresh
+    }
+}
+
+#[async_trait(?Send)]
+impl<S: KeySource + 'static> KeySource for CachedKeySource<S> {
+    async fn keys(&self) -> Result<Arc<KeySet>, KeySourceError> {
+        let now = Instant::now();
+        let (cached, refresh_at, expires_at) = {
+            let state = self.state.borrow();
+            (state.keys.clone(), state.refresh_at, state.expires_at)
+        };
+
+        let cached = match cached {
+            Some(cached) => cached,
+            None => return self.refresh().await,
+        };
+
+        if now < refresh_at {
+            return Ok(cached);
+        }
+
+        if now < expires_at {
+            actix_rt::spawn(self.refresh());
+            return Ok(cached);
+        }
+
+        // Stale keys are still better than failing every verification while the
+        // certificate endpoint is unavailable.
+        match self.refresh().await {
+            Ok(keys) => Ok(keys),
+            Err(_) => Ok(cached),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::{error, fmt};
+
+use awc::http::StatusCode;
+
+use crate::error::{FirebaseError, UserManagementError};
+
+#[derive(Debug, Clone)]
+pub enum KeySourceError {
+    Request(String),
+    Status(StatusCode),
+    Decoding(String),
+    InvalidCertificate(String),
+}
+
+impl fmt::Display for KeySourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeySourceError::Request(err) => write!(f, "Key request failed: {}", err),
+            KeySourceError::Status(status) => write!(f, "Key request returned {}", status),
+            KeySourceError::Decoding(err) => write!(f, "Key response decoding error: {}", err),
+            KeySourceError::InvalidCertificate(kid) => {
+                write!(f, "Invalid certificate for key {}", kid)
+            }
+        }
+    }
+}
+
+impl error::Error for KeySourceError {}
+
+#[derive(Debug)]
+pub enum VerifyError {
+    MissingProjectId,
+    Malformed,
+    InvalidAlgorithm,
+    MissingKeyId,
+    UnknownKeyId(String),
+    InvalidSignature,
+    Expired,
+    IssuedInFuture,
+    InvalidAuthTime,
+    InvalidAudience,
+    InvalidIssuer,
+    InvalidSubject,
+    KeySource(KeySourceError),
+    /// The user's tokens were revoked after this one was issued.
+    Revoked,
+    UserDisabled,
+    /// The user the token was issued to has been deleted.
+    UserNotFound,
+    /// The user could not be looked up to check for revocation.
+    UserLookup(FirebaseError<UserManagementError>),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyError::MissingProjectId => write!(f, "Project id is not configured"),
+            VerifyError::Malformed => write!(f, "Malformed token"),
+            VerifyError::InvalidAlgorithm => write!(f, "Invalid algorithm"),
+            VerifyError::MissingKeyId => write!(f, "Missing key id"),
+            VerifyError::UnknownKeyId(kid) => write!(f, "Unknown key id: {}", kid),
+            VerifyError::InvalidSignature => write!(f, "Invalid signature"),
+            VerifyError::Expired => write!(f, "Token expired"),
+            VerifyError::IssuedInFuture => write!(f, "Token issued in the future"),
+            VerifyError::InvalidAuthTime => write!(f, "Invalid auth time"),
+            VerifyError::InvalidAudience => write!(f, "Invalid audience"),
+            VerifyError::InvalidIssuer => write!(f, "Invalid issuer"),
+            VerifyError::InvalidSubject => write!(f, "Invalid subject"),
+            VerifyError::KeySource(err) => write!(f, "{}", err),
+            VerifyError::Revoked => write!(f, "Token revoked"),
+            VerifyError::UserDisabled => write!(f, "User disabled"),
+            VerifyError::UserNotFound => write!(f, "User not found"),
+            VerifyError::UserLookup(err) => write!(f, "User lookup failed: {}", err),
+        }
+    }
+}
+
+impl error::Error for VerifyError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            VerifyError::KeySource(err) => Some(err),
+            VerifyError::UserLookup(err) => Some(err),
+            _ => None,
+        }
+    }
+}
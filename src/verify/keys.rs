@@ -0,0 +1,133 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use awc::{
+    http::{header::CACHE_CONTROL, StatusCode},
+    Client,
+};
+use jsonwebtoken::DecodingKey;
+
+use super::error::KeySourceError;
+
+pub const ID_TOKEN_CERTS_URL: &str =
+    "https://www.googleapis.com/robot/v1/metadata/x509/securetoken@system.gserviceaccount.com";
+pub const SESSION_COOKIE_CERTS_URL: &str =
+    "https://www.googleapis.com/identitytoolkit/v3/relyingparty/publicKeys";
+
+/// Public keys used to check token signatures, indexed by key id (`kid`).
+#[derive(Clone, Default)]
+pub struct KeySet {
+    keys: HashMap<String, DecodingKey>,
+    max_age: Option<Duration>,
+}
+
+impl KeySet {
+    /// Builds a key set from a `kid -> PEM encoded x509 certificate` map, which is
+    /// the format served by Google's certificate endpoints.
+    pub fn from_pem_certificates(
+        certificates: &HashMap<String, String>,
+    ) -> Result<KeySet, KeySourceError> {
+        let mut keys = HashMap::new();
+        for (kid, pem) in certificates {
+            let key = DecodingKey::from_rsa_pem(pem.as_bytes())
+                .map_err(|_| KeySourceError::InvalidCertificate(kid.clone()))?;
+            keys.insert(kid.clone(), key);
+        }
+        Ok(KeySet {
+            keys,
+            max_age: None,
+        })
+    }
+
+    /// Sets how long the key set may be cached, usually taken from `Cache-Control: max-age`.
+    pub fn with_max_age(mut self, max_age: Duration) -> KeySet {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    pub fn insert(&mut self, kid: String, key: DecodingKey) {
+        self.keys.insert(kid, key);
+    }
+
+    pub fn get(&self, kid: &str) -> Option<&DecodingKey> {
+        self.keys.get(kid)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+/// Provides the key set tokens are verified against.
+///
+/// The default implementation downloads Google's certificates, but any source can be
+/// plugged into `Firebase::with_id_token_key_source`, e.g. locally generated keys in tests.
+#[async_trait(?Send)]
+pub trait KeySource {
+    async fn keys(&self) -> Result<Arc<KeySet>, KeySourceError>;
+}
+
+#[async_trait(?Send)]
+impl KeySource for KeySet {
+    async fn keys(&self) -> Result<Arc<KeySet>, KeySourceError> {
+        Ok(Arc::new(self.clone()))
+    }
+}
+
+/// Fetches x509 certificates from a Google metadata endpoint on every call.
+///
+/// Wrap it in a `CachedKeySource` to avoid a request per verified token.
+pub struct X509KeySource {
+    client: Arc<Client>,
+    url: String,
+}
+
+impl X509KeySource {
+    pub fn new(client: Arc<Client>, url: String) -> X509KeySource {
+        X509KeySource { client, url }
+    }
+}
+
+#[async_trait(?Send)]
+impl KeySource for X509KeySource {
+    async fn keys(&self) -> Result<Arc<KeySet>, KeySourceError> {
+        let mut response = self
+            .client
+            .get(self.url.as_str())
+            .send()
+            .await
+            .map_err(|err| KeySourceError::Request(err.to_string()))?;
+
+        match response.status() {
+            StatusCode::OK => {
+                let max_age = response
+                    .headers()
+                    .get(CACHE_CONTROL)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(parse_max_age);
+                let certificates = response
+                    .json::<HashMap<String, String>>()
+                    .await
+                    .map_err(|err| KeySourceError::Decoding(err.to_string()))?;
+                let keys = KeySet::from_pem_certificates(&certificates)?;
+                Ok(Arc::new(match max_age {
+                    Some(max_age) => keys.with_max_age(max_age),
+                    None => keys,
+                }))
+            }
+            status => Err(KeySourceError::Status(status)),
+        }
+    }
+}
+
+fn parse_max_age(cache_control: &str) -> Option<Duration> {
+    cache_control
+        .split(',')
+        .filter_map(|directive| directive.trim().strip_prefix("max-age="))
+        .find_map(|seconds| seconds.parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub aud: String,
+    pub sub: String,
+    pub iat: u64,
+    pub exp: u64,
+    pub auth_time: u64,
+    #[serde(default)]
+    pub user_id: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: Option<bool>,
+    #[serde(default)]
+    pub phone_number: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub picture: Option<String>,
+    pub firebase: FirebaseClaims,
+
+    #[serde(flatten)]
+    pub custom_claims: HashMap<String, serde_json::Value>,
+}
+
+impl IdTokenClaims {
+    pub fn uid(&self) -> &str {
+        &self.sub
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FirebaseClaims {
+    #[serde(default)]
+    pub identities: HashMap<String, Vec<String>>,
+    pub sign_in_provider: String,
+    #[serde(default)]
+    pub sign_in_second_factor: Option<String>,
+    #[serde(default)]
+    pub tenant: Option<String>,
+}
+
+/// Additional checks made by `Firebase::verify_id_token_with` and
+/// `Firebase::verify_session_cookie_with`.
+#[derive(Debug, Clone, Default)]
+pub struct VerifyOptions {
+    /// Looks up the user to reject tokens of deleted or disabled users, or issued before the
+    /// user's tokens were revoked. Requires admin credentials and costs a request per verification.
+    pub check_revoked: bool,
+}
@@ -0,0 +1,180 @@
+mod common;
+
+use std::{rc::Rc, sync::Arc};
+
+use actix_web::{
+    http::{header, StatusCode},
+    test, web, App, HttpResponse,
+};
+use async_trait::async_trait;
+use awc_firebase_auth::{
+    actix::{AuthConfig, AuthRejection, AuthenticatedUser, FirebaseAuth},
+    verify::{KeySet, KeySource, KeySourceError},
+};
+use common::{firebase, sign, valid_claims, KEY_ID, OTHER_KEY, SIGNING_KEY};
+use serde_json::{json, Value};
+
+async fn whoami(user: AuthenticatedUser) -> HttpResponse {
+    HttpResponse::Ok().body(user.uid().to_owned())
+}
+
+/// A key source whose certificate endpoint is down.
+struct UnavailableKeySource;
+
+#[async_trait(?Send)]
+impl KeySource for UnavailableKeySource {
+    async fn keys(&self) -> Result<Arc<KeySet>, KeySourceError> {
+        Err(KeySourceError::Request("unavailable".to_owned()))
+    }
+}
+
+fn bearer(token: &str) -> (header::HeaderName, String) {
+    (header::AUTHORIZATION, format!("Bearer {}", token))
+}
+
+#[actix_rt::test]
+async fn extractor_authenticates_bearer_token() {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(firebase()))
+            .route("/", web::get().to(whoami)),
+    )
+    .await;
+
+    let token = sign(&valid_claims(), SIGNING_KEY, KEY_ID);
+    let req = test::TestRequest::get()
+        .uri("/")
+        .insert_header(bearer(&token))
+        .to_request();
+    let body = test::call_and_read_body(&app, req).await;
+    assert_eq!(body, "user-1");
+
+    let req = test::TestRequest::get().uri("/").to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        res.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+        "Bearer"
+    );
+}
+
+#[actix_rt::test]
+async fn middleware_rejects_invalid_tokens() {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(firebase()))
+            .wrap(FirebaseAuth::default())
+            .route("/", web::get().to(whoami)),
+    )
+    .await;
+
+    let token = sign(&valid_claims(), OTHER_KEY, KEY_ID);
+    let req = test::TestRequest::get()
+        .uri("/")
+        .insert_header(bearer(&token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let body: Value = test::read_body_json(res).await;
+    assert_eq!(body["error"]["message"], "Invalid token: Invalid signature");
+}
+
+#[actix_rt::test]
+async fn middleware_uses_configured_error_body() {
+    let config = AuthConfig::default()
+        .require_email_verified(true)
+        .error_handler(|rejection: &AuthRejection| {
+            HttpResponse::build(rejection.status_code())
+                .json(json!({ "reason": rejection.to_string() }))
+        });
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(firebase()))
+            .wrap(FirebaseAuth::with_config(config))
+            .route("/", web::get().to(whoami)),
+    )
+    .await;
+
+    let mut claims = valid_claims();
+    claims["email_verified"] = json!(false);
+    let token = sign(&claims, SIGNING_KEY, KEY_ID);
+    let req = test::TestRequest::get()
+        .uri("/")
+        .insert_header(bearer(&token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let body: Value = test::read_body_json(res).await;
+    assert_eq!(body["reason"], "Email not verified");
+}
+
+#[actix_rt::test]
+async fn revocation_check_fails_without_admin_credentials() {
+    let config = AuthConfig::default().check_revoked(true);
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(firebase()))
+            .app_data(config)
+            .route("/", web::get().to(whoami)),
+    )
+    .await;
+
+    // Without a service account the user can't be looked up, so revocation can't be checked.
+    let token = sign(&valid_claims(), SIGNING_KEY, KEY_ID);
+    let req = test::TestRequest::get()
+        .uri("/")
+        .insert_header(bearer(&token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    let body: Value = test::read_body_json(res).await;
+    assert_eq!(
+        body["error"]["message"],
+        "Invalid token: User lookup failed: Missing admin credentials"
+    );
+}
+
+#[actix_rt::test]
+async fn extractor_applies_its_config_to_middleware_user() {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(firebase()))
+            .app_data(AuthConfig::default().require_email_verified(true))
+            .wrap(FirebaseAuth::with_config(AuthConfig::default()))
+            .route("/", web::get().to(whoami)),
+    )
+    .await;
+
+    let mut claims = valid_claims();
+    claims["email_verified"] = json!(false);
+    let token = sign(&claims, SIGNING_KEY, KEY_ID);
+    let req = test::TestRequest::get()
+        .uri("/")
+        .insert_header(bearer(&token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_rt::test]
+async fn key_source_failures_are_not_blamed_on_the_token() {
+    let firebase = firebase().with_id_token_key_source(Rc::new(UnavailableKeySource));
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(firebase))
+            .route("/", web::get().to(whoami)),
+    )
+    .await;
+
+    let token = sign(&valid_claims(), SIGNING_KEY, KEY_ID);
+    let req = test::TestRequest::get()
+        .uri("/")
+        .insert_header(bearer(&token))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(res.headers().get(header::WWW_AUTHENTICATE).is_none());
+}
@@ -0,0 +1,81 @@
+#![allow(dead_code, clippy::arc_with_non_send_sync)]
+
+use std::{
+    collections::HashMap,
+    rc::Rc,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use awc::Client;
+use awc_firebase_auth::{verify::KeySet, Firebase};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde_json::{json, Value};
+
+pub const SIGNING_KEY: &str = include_str!("../fixtures/signing_key.pem");
+pub const OTHER_KEY: &str = include_str!("../fixtures/other_key.pem");
+
+pub const PROJECT_ID: &str = "test-project";
+pub const KEY_ID: &str = "test-key";
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+pub fn valid_claims() -> Value {
+    let now = now();
+    json!({
+        "iss": format!("https://securetoken.google.com/{}", PROJECT_ID),
+        "aud": PROJECT_ID,
+        "sub": "user-1",
+        "user_id": "user-1",
+        "iat": now - 10,
+        "exp": now + 3600,
+        "auth_time": now - 10,
+        "email": "user@example.com",
+        "email_verified": true,
+        "role": "admin",
+        "firebase": {
+            "identities": { "email": ["user@example.com"] },
+            "sign_in_provider": "password"
+        }
+    })
+}
+
+pub fn sign(claims: &Value, key: &str, kid: &str) -> String {
+    let mut header = Header::new(Algorithm::RS256);
+    header.kid = Some(kid.to_owned());
+    let key = EncodingKey::from_rsa_pem(key.as_bytes()).unwrap();
+    jsonwebtoken::encode(&header, claims, &key).unwrap()
+}
+
+/// Encodes `claims` the way the Auth emulator does, with `alg: none` and no signature.
+pub fn unsigned(claims: &Value) -> String {
+    let header = URL_SAFE_NO_PAD.encode(json!({ "alg": "none", "typ": "JWT" }).to_string());
+    let payload = URL_SAFE_NO_PAD.encode(claims.to_string());
+    format!("{}.{}.", header, payload)
+}
+
+pub fn keys() -> KeySet {
+    let mut certificates = HashMap::new();
+    certificates.insert(
+        KEY_ID.to_owned(),
+        include_str!("../fixtures/signing_cert.pem").to_owned(),
+    );
+    KeySet::from_pem_certificates(&certificates).unwrap()
+}
+
+pub fn firebase() -> Firebase {
+    Firebase::auth(
+        "http://localhost".to_owned(),
+        "api-key".to_owned(),
+        Arc::new(Client::default()),
+    )
+    .without_emulator()
+    .with_project_id(PROJECT_ID.to_owned())
+    .with_id_token_key_source(Rc::new(keys()))
+}
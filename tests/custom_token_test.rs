@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use awc_firebase_auth::admin::{CreateCustomTokenError, CustomTokenOptions, ServiceAccount};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde_json::{json, Value};
+
+const AUDIENCE: &str =
+    "https://identitytoolkit.googleapis.com/google.identity.identitytoolkit.v1.IdentityToolkit";
+
+fn service_account() -> ServiceAccount {
+    ServiceAccount::from_file("tests/fixtures/service_account.json").unwrap()
+}
+
+#[test]
+fn creates_signed_custom_token() {
+    let options = CustomTokenOptions {
+        claims: json!({ "premium": true }).as_object().cloned(),
+        tenant_id: Some("tenant-1".to_owned()),
+        ..CustomTokenOptions::default()
+    };
+    let token = service_account()
+        .create_custom_token("user-1", &options)
+        .unwrap();
+
+    let key = DecodingKey::from_rsa_pem(include_bytes!("fixtures/signing_cert.pem")).unwrap();
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.set_audience(&[AUDIENCE]);
+    let claims = jsonwebtoken::decode::<Value>(&token, &key, &validation)
+        .unwrap()
+        .claims;
+
+    let email = "firebase-adminsdk@test-project.iam.gserviceaccount.com";
+    assert_eq!(claims["iss"], email);
+    assert_eq!(claims["sub"], email);
+    assert_eq!(claims["uid"], "user-1");
+    assert_eq!(claims["claims"]["premium"], true);
+    assert_eq!(claims["tenant_id"], "tenant-1");
+    assert_eq!(
+        claims["exp"].as_u64().unwrap() - claims["iat"].as_u64().unwrap(),
+        3600
+    );
+}
+
+#[test]
+fn validates_custom_token_options() {
+    let service_account = service_account();
+
+    let err = service_account
+        .create_custom_token("", &CustomTokenOptions::default())
+        .unwrap_err();
+    assert!(matches!(err, CreateCustomTokenError::InvalidUid));
+
+    let reserved = CustomTokenOptions {
+        claims: json!({ "firebase": {} }).as_object().cloned(),
+        ..CustomTokenOptions::default()
+    };
+    let err = service_account
+        .create_custom_token("user-1", &reserved)
+        .unwrap_err();
+    assert!(matches!(err, CreateCustomTokenError::ReservedClaim(claim) if claim == "firebase"));
+
+    let too_long = CustomTokenOptions {
+        expires_in: Duration::from_secs(3601),
+        ..CustomTokenOptions::default()
+    };
+    let err = service_account
+        .create_custom_token("user-1", &too_long)
+        .unwrap_err();
+    assert!(matches!(err, CreateCustomTokenError::InvalidLifetime));
+}
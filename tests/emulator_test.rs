@@ -0,0 +1,30 @@
+#![allow(clippy::arc_with_non_send_sync)]
+
+mod common;
+
+use common::{firebase, unsigned, valid_claims};
+use serde_json::json;
+
+#[actix_rt::test]
+async fn emulator_accepts_unsigned_tokens() {
+    let token = unsigned(&valid_claims());
+
+    let emulator = firebase().with_emulator("localhost:9099".to_owned());
+    let claims = emulator.verify_id_token(&token).await.unwrap();
+    assert_eq!(claims.uid(), "user-1");
+
+    let mut expired = valid_claims();
+    expired["exp"] = json!(0);
+    let err = emulator
+        .verify_id_token(&unsigned(&expired))
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Token expired");
+}
+
+#[test]
+fn emulator_can_be_turned_off() {
+    let emulator = firebase().with_emulator("localhost:9099".to_owned());
+    assert!(emulator.is_emulator());
+    assert!(!emulator.without_emulator().is_emulator());
+}
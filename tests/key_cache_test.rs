@@ -0,0 +1,95 @@
+use std::{cell::Cell, rc::Rc, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use awc_firebase_auth::verify::{CachedKeySource, KeySet, KeySource, KeySourceError};
+use jsonwebtoken::DecodingKey;
+
+/// Counts fetches and fails them while `failing` is set.
+struct CountingSource {
+    fetches: Rc<Cell<usize>>,
+    failing: Rc<Cell<bool>>,
+    max_age: Duration,
+}
+
+#[async_trait(?Send)]
+impl KeySource for CountingSource {
+    async fn keys(&self) -> Result<Arc<KeySet>, KeySourceError> {
+        self.fetches.set(self.fetches.get() + 1);
+        actix_rt::time::sleep(Duration::from_millis(10)).await;
+        if self.failing.get() {
+            return Err(KeySourceError::Request("unavailable".to_owned()));
+        }
+        let mut keys = KeySet::default();
+        keys.insert(
+            format!("key-{}", self.fetches.get()),
+            DecodingKey::from_secret(b"secret"),
+        );
+        Ok(Arc::new(keys.with_max_age(self.max_age)))
+    }
+}
+
+fn source(max_age: Duration) -> (CountingSource, Rc<Cell<usize>>, Rc<Cell<bool>>) {
+    let fetches = Rc::new(Cell::new(0));
+    let failing = Rc::new(Cell::new(false));
+    let source = CountingSource {
+        fetches: fetches.clone(),
+        failing: failing.clone(),
+        max_age,
+    };
+    (source, fetches, failing)
+}
+
+#[actix_rt::test]
+async fn caches_keys_for_max_age() {
+    let (source, fetches, _) = source(Duration::from_secs(3600));
+    let cache = CachedKeySource::new(source);
+
+    cache.keys().await.unwrap();
+    cache.keys().await.unwrap();
+
+    assert_eq!(fetches.get(), 1);
+}
+
+#[actix_rt::test]
+async fn deduplicates_concurrent_refreshes() {
+    let (source, fetches, _) = source(Duration::from_secs(3600));
+    let cache = CachedKeySource::new(source);
+
+    let (first, second) = futures_util::join!(cache.keys(), cache.keys());
+
+    assert!(first.unwrap().get("key-1").is_some());
+    assert!(second.unwrap().get("key-1").is_some());
+    assert_eq!(fetches.get(), 1);
+}
+
+#[actix_rt::test]
+async fn refreshes_in_background_before_expiry() {
+    let (source, fetches, _) = source(Duration::from_secs(60));
+    let cache = CachedKeySource::new(source).with_refresh_window(Duration::from_secs(60));
+
+    cache.keys().await.unwrap();
+    let keys = cache.keys().await.unwrap();
+    assert!(keys.get("key-1").is_some());
+
+    actix_rt::time::sleep(Duration::from_millis(50)).await;
+    let keys = cache.keys().await.unwrap();
+    assert!(keys.get("key-2").is_some());
+    assert_eq!(fetches.get(), 2);
+}
+
+#[actix_rt::test]
+async fn serves_stale_keys_when_refresh_fails() {
+    let (source, fetches, failing) = source(Duration::ZERO);
+    let cache = CachedKeySource::new(source);
+
+    cache.keys().await.unwrap();
+    failing.set(true);
+
+    let keys = cache.keys().await.unwrap();
+    assert!(keys.get("key-1").is_some());
+    assert_eq!(fetches.get(), 2);
+
+    // Failed refreshes are not retried on every call.
+    cache.keys().await.unwrap();
+    assert_eq!(fetches.get(), 2);
+}
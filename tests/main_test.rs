@@ -1,8 +1,1307 @@
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn it_works() {
-        let result = 2 + 2;
-        assert_eq!(result, 4);
+#![allow(clippy::arc_with_non_send_sync)]
+
+mod common;
+
+use std::{
+    cell::Cell,
+    env,
+    error::Error,
+    fs,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use awc::{http::StatusCode, Client};
+use awc_firebase_auth::{
+    action_code::{ActionCodeLink, ActionCodeMode, ActionCodeSettings},
+    admin::{CustomTokenOptions, ServiceAccount},
+    error::{
+        AccountError, ActionCodeError, AuthErrorCode, CustomTokenError, EmailLinkError,
+        ErrorContainer, LinkError, LoginError, MfaError, PasswordResetError, PhoneAuthError,
+        RefreshTokenError, RegisterError, SessionCookieError, UpdateCredentialError,
+        UserManagementError,
+    },
+    oauth::{model::OAuthToken, Provider},
+    session::{JsonFileSessionStore, MemorySessionStore, Session, SessionStore},
+    testing::{
+        FakeIdpAccount, FakeMfaEnrollment, FakeMfaFactor, FakeServer, FAKE_PROJECT_ID,
+        FAKE_TOTP_CODE,
+    },
+    verify::{VerifyError, VerifyOptions},
+    Firebase, MfaEnrollmentRequest, MfaEnrollmentSession, MfaEnrollmentVerification,
+    MfaVerification, ProfileUpdate, SignInMethod, UserRecord,
+};
+use common::unsigned;
+use futures_util::future::join;
+use serde_json::json;
+
+fn start() -> (FakeServer, Firebase) {
+    let server = FakeServer::start().unwrap();
+    let firebase = server.firebase(Arc::new(Client::default()));
+    (server, firebase)
+}
+
+#[actix_rt::test]
+async fn register_login_refresh_and_delete() {
+    let (server, firebase) = start();
+
+    let registered = firebase
+        .register("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+    assert_eq!(registered.email, "user@example.com");
+
+    let login = firebase
+        .login("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+    assert_eq!(login.local_id, registered.local_id);
+
+    let claims = firebase.verify_id_token(&login.id_token).await.unwrap();
+    assert_eq!(claims.uid(), registered.local_id);
+    assert_eq!(claims.firebase.sign_in_provider, "password");
+
+    let refreshed = firebase
+        .exchange_refresh_token(login.refresh_token)
+        .await
+        .unwrap();
+    assert_eq!(refreshed.user_id, registered.local_id);
+
+    firebase.delete_account(refreshed.id_token).await.unwrap();
+    assert!(server.user(&registered.local_id).is_none());
+    assert!(matches!(
+        firebase
+            .login("user@example.com".to_owned(), "password".to_owned())
+            .await
+            .unwrap_err()
+            .kind(),
+        LoginError::EmailNotFound
+    ));
+}
+
+#[actix_rt::test]
+async fn maps_firebase_errors() {
+    let (server, firebase) = start();
+    let registered = firebase
+        .register("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        firebase
+            .register("user@example.com".to_owned(), "password".to_owned())
+            .await
+            .unwrap_err()
+            .kind(),
+        RegisterError::EmailExists
+    ));
+    assert!(matches!(
+        firebase
+            .login("user@example.com".to_owned(), "wrong".to_owned())
+            .await
+            .unwrap_err()
+            .kind(),
+        LoginError::InvalidPassword
+    ));
+    assert!(matches!(
+        firebase
+            .delete_account("invalid".to_owned())
+            .await
+            .unwrap_err()
+            .kind(),
+        AccountError::InvalidIdToken
+    ));
+
+    server.set_disabled(&registered.local_id, true);
+    assert!(matches!(
+        firebase
+            .exchange_refresh_token(registered.refresh_token)
+            .await
+            .unwrap_err()
+            .kind(),
+        RefreshTokenError::UserDisabled
+    ));
+}
+
+#[actix_rt::test]
+async fn sends_verification_email() {
+    let (server, firebase) = start();
+    let registered = firebase
+        .register("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+
+    firebase
+        .send_verification_email(registered.id_token)
+        .await
+        .unwrap();
+
+    let codes = server.oob_codes();
+    assert_eq!(codes.len(), 1);
+    assert_eq!(codes[0].request_type, "VERIFY_EMAIL");
+    assert_eq!(codes[0].email, "user@example.com");
+}
+
+#[actix_rt::test]
+async fn signs_in_with_idp() {
+    let (server, firebase) = start();
+    server.add_idp_account(
+        Provider::Google,
+        "google-id-token",
+        FakeIdpAccount {
+            federated_id: "google-user".to_owned(),
+            email: "user@gmail.com".to_owned(),
+            full_name: Some("Google User".to_owned()),
+        },
+    );
+    let token = OAuthToken {
+        token: "google-id-token".to_owned(),
+        provider: Provider::Google,
+        nonce: None,
+    };
+
+    let first = firebase
+        .sign_in_with_idp("http://localhost", &token)
+        .await
+        .unwrap();
+    let second = firebase
+        .sign_in_with_idp("http://localhost", &token)
+        .await
+        .unwrap();
+
+    assert_eq!(first.local_id, second.local_id);
+    assert_eq!(first.full_name.as_deref(), Some("Google User"));
+    assert_eq!(
+        server.user(&first.local_id).unwrap().email.as_deref(),
+        Some("user@gmail.com")
+    );
+}
+
+#[actix_rt::test]
+async fn upgrades_anonymous_account_with_password() {
+    let (server, firebase) = start();
+
+    let anonymous = firebase.sign_in_anonymously().await.unwrap();
+    let claims = firebase.verify_id_token(&anonymous.id_token).await.unwrap();
+    assert_eq!(claims.firebase.sign_in_provider, "anonymous");
+
+    let linked = firebase
+        .link_with_email_password(
+            anonymous.id_token,
+            "user@example.com".to_owned(),
+            "password".to_owned(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(linked.local_id, anonymous.local_id);
+
+    let login = firebase
+        .login("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+    assert_eq!(login.local_id, anonymous.local_id);
+    assert!(server.user(&anonymous.local_id).unwrap().password.is_some());
+}
+
+#[actix_rt::test]
+async fn upgrades_anonymous_account_with_idp() {
+    let (server, firebase) = start();
+    server.add_idp_account(
+        Provider::Facebook,
+        "facebook-access-token",
+        FakeIdpAccount {
+            federated_id: "facebook-user".to_owned(),
+            email: "user@facebook.com".to_owned(),
+            full_name: None,
+        },
+    );
+    let token = OAuthToken {
+        token: "facebook-access-token".to_owned(),
+        provider: Provider::Facebook,
+        nonce: None,
+    };
+
+    let anonymous = firebase.sign_in_anonymously().await.unwrap();
+    let linked = firebase
+        .link_with_idp(anonymous.id_token, "http://localhost", &token)
+        .await
+        .unwrap();
+    assert_eq!(linked.local_id, anonymous.local_id);
+
+    let signed_in = firebase
+        .sign_in_with_idp("http://localhost", &token)
+        .await
+        .unwrap();
+    assert_eq!(signed_in.local_id, anonymous.local_id);
+}
+
+#[actix_rt::test]
+async fn signs_in_with_custom_token() {
+    let (_server, firebase) = start();
+    let token = unsigned(&json!({
+        "aud": "https://identitytoolkit.googleapis.com/google.identity.identitytoolkit.v1.IdentityToolkit",
+        "uid": "legacy-user",
+        "claims": { "premium": true },
+    }));
+
+    let first = firebase
+        .sign_in_with_custom_token(token.clone())
+        .await
+        .unwrap();
+    assert!(first.is_new_user);
+    let claims = firebase.verify_id_token(&first.id_token).await.unwrap();
+    assert_eq!(claims.uid(), "legacy-user");
+    assert_eq!(claims.custom_claims.get("premium"), Some(&json!(true)));
+
+    let second = firebase.sign_in_with_custom_token(token).await.unwrap();
+    assert!(!second.is_new_user);
+
+    assert!(matches!(
+        firebase
+            .sign_in_with_custom_token("garbage".to_owned())
+            .await
+            .unwrap_err()
+            .kind(),
+        CustomTokenError::InvalidCustomToken
+    ));
+}
+
+#[actix_rt::test]
+async fn signs_in_with_minted_custom_token() {
+    let (_server, firebase) = start();
+    let service_account = ServiceAccount::from_file("tests/fixtures/service_account.json").unwrap();
+    let options = CustomTokenOptions {
+        claims: json!({ "role": "admin" }).as_object().cloned(),
+        ..CustomTokenOptions::default()
+    };
+    let token = service_account
+        .create_custom_token("sso-user", &options)
+        .unwrap();
+
+    let session = firebase.sign_in_with_custom_token(token).await.unwrap();
+    let claims = firebase.verify_id_token(&session.id_token).await.unwrap();
+    assert_eq!(claims.uid(), "sso-user");
+    assert_eq!(claims.custom_claims.get("role"), Some(&json!("admin")));
+}
+
+#[actix_rt::test]
+async fn resets_password() {
+    let (server, firebase) = start();
+    firebase
+        .register("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        firebase
+            .send_password_reset_email("unknown@example.com".to_owned())
+            .await
+            .unwrap_err()
+            .kind(),
+        PasswordResetError::EmailNotFound
+    ));
+    firebase
+        .send_password_reset_email("user@example.com".to_owned())
+        .await
+        .unwrap();
+    let oob_code = server.oob_codes()[0].oob_code.clone();
+
+    let email = firebase
+        .verify_password_reset_code(oob_code.clone())
+        .await
+        .unwrap();
+    assert_eq!(email, "user@example.com");
+
+    assert!(matches!(
+        firebase
+            .confirm_password_reset(oob_code.clone(), "short".to_owned())
+            .await
+            .unwrap_err()
+            .kind(),
+        PasswordResetError::WeakPassword
+    ));
+    firebase
+        .confirm_password_reset(oob_code.clone(), "new-password".to_owned())
+        .await
+        .unwrap();
+    assert!(matches!(
+        firebase
+            .verify_password_reset_code(oob_code)
+            .await
+            .unwrap_err()
+            .kind(),
+        PasswordResetError::InvalidOobCode
+    ));
+
+    firebase
+        .login("user@example.com".to_owned(), "new-password".to_owned())
+        .await
+        .unwrap();
+}
+
+#[actix_rt::test]
+async fn signs_in_with_email_link() {
+    let (server, firebase) = start();
+    let settings = ActionCodeSettings {
+        continue_url: "https://example.com/finish".to_owned(),
+        can_handle_code_in_app: true,
+    };
+
+    firebase
+        .send_sign_in_link_to_email("user@example.com".to_owned(), &settings)
+        .await
+        .unwrap();
+
+    let link = ActionCodeLink::parse(&server.oob_codes()[0].link()).unwrap();
+    assert!(link.is_sign_in());
+    assert_eq!(
+        link.continue_url.as_deref(),
+        Some("https://example.com/finish")
+    );
+
+    let session = firebase
+        .sign_in_with_email_link("user@example.com".to_owned(), link.oob_code.clone())
+        .await
+        .unwrap();
+    assert!(session.is_new_user);
+    let claims = firebase.verify_id_token(&session.id_token).await.unwrap();
+    assert_eq!(claims.email_verified, Some(true));
+
+    assert!(matches!(
+        firebase
+            .sign_in_with_email_link("user@example.com".to_owned(), link.oob_code)
+            .await
+            .unwrap_err()
+            .kind(),
+        EmailLinkError::InvalidOobCode
+    ));
+}
+
+#[actix_rt::test]
+async fn looks_up_account_info() {
+    let (_server, firebase) = start();
+    let registered = firebase
+        .register("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+
+    let user = firebase
+        .get_account_info(registered.id_token)
+        .await
+        .unwrap();
+    assert_eq!(user.local_id, registered.local_id);
+    assert_eq!(user.email.as_deref(), Some("user@example.com"));
+    assert!(!user.email_verified);
+    assert_eq!(user.provider_user_info[0].provider_id, "password");
+    assert!(user.created_at.unwrap() <= user.last_login_at.unwrap());
+
+    assert!(matches!(
+        firebase
+            .get_account_info("invalid".to_owned())
+            .await
+            .unwrap_err()
+            .kind(),
+        AccountError::InvalidIdToken
+    ));
+}
+
+#[test]
+fn parses_user_record_timestamps_and_attributes() {
+    let user: UserRecord = serde_json::from_value(json!({
+        "localId": "uid",
+        "createdAt": "1700000000000",
+        "lastRefreshAt": "2023-11-14T22:13:20.500Z",
+        "validSince": "1700000000",
+        "customAttributes": "{\"admin\":true}",
+        "mfaInfo": [{
+            "mfaEnrollmentId": "enrollment",
+            "totpInfo": {},
+            "enrolledAt": "2023-11-14T22:13:20Z",
+        }],
+    }))
+    .unwrap();
+
+    let expected = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    assert_eq!(user.created_at, Some(expected));
+    assert_eq!(user.valid_since, Some(expected));
+    assert_eq!(
+        user.last_refresh_at,
+        Some(expected + Duration::from_millis(500))
+    );
+    assert_eq!(user.custom_attributes.unwrap()["admin"], json!(true));
+    assert!(user.mfa_info[0].is_totp());
+    assert_eq!(user.mfa_info[0].enrolled_at, Some(expected));
+}
+
+#[actix_rt::test]
+async fn updates_and_deletes_profile_attributes() {
+    let (_server, firebase) = start();
+    let registered = firebase
+        .register("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+
+    let updated = firebase
+        .update_profile(
+            registered.id_token,
+            ProfileUpdate::new()
+                .display_name("Jane".to_owned())
+                .photo_url("https://example.com/jane.png".to_owned()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(updated.display_name.as_deref(), Some("Jane"));
+    assert_eq!(
+        updated.photo_url.as_deref(),
+        Some("https://example.com/jane.png")
+    );
+
+    let updated = firebase
+        .update_profile(updated.id_token, ProfileUpdate::new().delete_photo_url())
+        .await
+        .unwrap();
+    assert_eq!(updated.display_name.as_deref(), Some("Jane"));
+    assert_eq!(updated.photo_url, None);
+
+    let user = firebase.get_account_info(updated.id_token).await.unwrap();
+    assert_eq!(user.display_name.as_deref(), Some("Jane"));
+    assert_eq!(user.photo_url, None);
+}
+
+#[actix_rt::test]
+async fn changes_email_and_password() {
+    let (_server, firebase) = start();
+    firebase
+        .register("taken@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+    let registered = firebase
+        .register("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        firebase
+            .change_email(registered.id_token.clone(), "taken@example.com".to_owned())
+            .await
+            .unwrap_err()
+            .kind(),
+        UpdateCredentialError::EmailExists
+    ));
+    assert!(matches!(
+        firebase
+            .change_password(registered.id_token.clone(), "short".to_owned())
+            .await
+            .unwrap_err()
+            .kind(),
+        UpdateCredentialError::WeakPassword
+    ));
+
+    let changed = firebase
+        .change_email(registered.id_token, "new@example.com".to_owned())
+        .await
+        .unwrap();
+    assert_eq!(changed.email.as_deref(), Some("new@example.com"));
+    let changed = firebase
+        .change_password(changed.id_token, "new-password".to_owned())
+        .await
+        .unwrap();
+    assert_eq!(changed.local_id, registered.local_id);
+
+    let login = firebase
+        .login("new@example.com".to_owned(), "new-password".to_owned())
+        .await
+        .unwrap();
+    assert_eq!(login.local_id, registered.local_id);
+}
+
+#[actix_rt::test]
+async fn verifies_and_changes_email() {
+    let (server, firebase) = start();
+    let registered = firebase
+        .register("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+
+    firebase
+        .send_verification_email(registered.id_token.clone())
+        .await
+        .unwrap();
+    let applied = firebase
+        .apply_action_code(server.oob_codes()[0].oob_code.clone())
+        .await
+        .unwrap();
+    assert_eq!(applied.mode(), ActionCodeMode::VerifyEmail);
+    assert!(server.user(&registered.local_id).unwrap().email_verified);
+
+    firebase
+        .send_verify_and_change_email(registered.id_token, "new@example.com".to_owned(), None)
+        .await
+        .unwrap();
+    let code = server.oob_codes()[0].clone();
+    assert_eq!(code.new_email.as_deref(), Some("new@example.com"));
+    assert_eq!(
+        server.user(&registered.local_id).unwrap().email.as_deref(),
+        Some("user@example.com")
+    );
+
+    let applied = firebase
+        .apply_action_code(code.oob_code.clone())
+        .await
+        .unwrap();
+    assert_eq!(applied.mode(), ActionCodeMode::VerifyAndChangeEmail);
+    assert_eq!(applied.new_email.as_deref(), Some("new@example.com"));
+    assert_eq!(
+        server.user(&registered.local_id).unwrap().email.as_deref(),
+        Some("new@example.com")
+    );
+
+    assert!(matches!(
+        firebase
+            .apply_action_code(code.oob_code)
+            .await
+            .unwrap_err()
+            .kind(),
+        ActionCodeError::InvalidOobCode
+    ));
+}
+
+#[actix_rt::test]
+async fn fetches_sign_in_methods() {
+    let (server, firebase) = start();
+    server.add_idp_account(
+        Provider::Google,
+        "google-id-token",
+        FakeIdpAccount {
+            federated_id: "google-user".to_owned(),
+            email: "user@gmail.com".to_owned(),
+            full_name: None,
+        },
+    );
+    let token = OAuthToken {
+        token: "google-id-token".to_owned(),
+        provider: Provider::Google,
+        nonce: None,
+    };
+    firebase
+        .sign_in_with_idp("http://localhost", &token)
+        .await
+        .unwrap();
+    firebase
+        .register("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+    let continue_uri = "http://localhost".to_owned();
+
+    let methods = firebase
+        .fetch_sign_in_methods("user@gmail.com".to_owned(), continue_uri.clone())
+        .await
+        .unwrap();
+    assert!(methods.registered);
+    assert_eq!(
+        methods.signin_methods,
+        vec![SignInMethod::Idp(Provider::Google)]
+    );
+
+    let methods = firebase
+        .fetch_sign_in_methods("user@example.com".to_owned(), continue_uri.clone())
+        .await
+        .unwrap();
+    assert_eq!(methods.all_providers, vec![SignInMethod::Password]);
+
+    let methods = firebase
+        .fetch_sign_in_methods("unknown@example.com".to_owned(), continue_uri)
+        .await
+        .unwrap();
+    assert!(!methods.registered);
+    assert!(methods.signin_methods.is_empty());
+}
+
+#[actix_rt::test]
+async fn links_and_unlinks_idp_on_existing_account() {
+    let (server, firebase) = start();
+    server.add_idp_account(
+        Provider::Google,
+        "google-id-token",
+        FakeIdpAccount {
+            federated_id: "google-user".to_owned(),
+            email: "user@gmail.com".to_owned(),
+            full_name: None,
+        },
+    );
+    let token = OAuthToken {
+        token: "google-id-token".to_owned(),
+        provider: Provider::Google,
+        nonce: None,
+    };
+    let owner = firebase
+        .register("owner@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+    let other = firebase
+        .register("other@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+
+    let linked = firebase
+        .link_with_idp(owner.id_token, "http://localhost", &token)
+        .await
+        .unwrap();
+    assert_eq!(linked.local_id, owner.local_id);
+    assert!(matches!(
+        firebase
+            .link_with_idp(other.id_token.clone(), "http://localhost", &token)
+            .await
+            .unwrap_err()
+            .kind(),
+        LinkError::FederatedUserIdAlreadyLinked
+    ));
+
+    let unlinked = firebase
+        .unlink_providers(linked.id_token, &[Provider::Google])
+        .await
+        .unwrap();
+    assert!(unlinked
+        .provider_user_info
+        .iter()
+        .all(|provider| provider.provider_id == "password"));
+
+    let relinked = firebase
+        .link_with_idp(other.id_token, "http://localhost", &token)
+        .await
+        .unwrap();
+    assert_eq!(relinked.local_id, other.local_id);
+}
+
+#[actix_rt::test]
+async fn signs_in_and_links_with_phone_number() {
+    let (server, firebase) = start();
+
+    assert!(matches!(
+        firebase
+            .send_phone_verification_code("0155 5555".to_owned(), "recaptcha".to_owned())
+            .await
+            .unwrap_err()
+            .kind(),
+        PhoneAuthError::InvalidPhoneNumber
+    ));
+
+    let session_info = firebase
+        .send_phone_verification_code("+15555550100".to_owned(), "recaptcha".to_owned())
+        .await
+        .unwrap();
+    assert!(matches!(
+        firebase
+            .sign_in_with_phone_number(session_info.clone(), "000000".to_owned())
+            .await
+            .unwrap_err()
+            .kind(),
+        PhoneAuthError::InvalidCode
+    ));
+    let code = server.phone_codes()[0].code.clone();
+    let signed_in = firebase
+        .sign_in_with_phone_number(session_info, code)
+        .await
+        .unwrap();
+    assert!(signed_in.is_new_user);
+    let claims = firebase.verify_id_token(&signed_in.id_token).await.unwrap();
+    assert_eq!(claims.phone_number.as_deref(), Some("+15555550100"));
+    assert_eq!(claims.firebase.sign_in_provider, "phone");
+
+    let registered = firebase
+        .register("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+    let session_info = firebase
+        .send_phone_verification_code("+15555550101".to_owned(), "recaptcha".to_owned())
+        .await
+        .unwrap();
+    let code = server.phone_codes()[0].code.clone();
+    let linked = firebase
+        .link_with_phone_number(registered.id_token, session_info, code)
+        .await
+        .unwrap();
+    assert_eq!(linked.local_id, registered.local_id);
+    assert_eq!(
+        server
+            .user(&registered.local_id)
+            .unwrap()
+            .phone_number
+            .as_deref(),
+        Some("+15555550101")
+    );
+}
+
+#[actix_rt::test]
+async fn completes_sign_in_with_second_factor() {
+    let (server, firebase) = start();
+    let registered = firebase
+        .register("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+    server.add_mfa_enrollment(
+        &registered.local_id,
+        FakeMfaEnrollment {
+            mfa_enrollment_id: "sms-factor".to_owned(),
+            display_name: Some("Work phone".to_owned()),
+            factor: FakeMfaFactor::Phone("+15555550100".to_owned()),
+        },
+    );
+    server.add_mfa_enrollment(
+        &registered.local_id,
+        FakeMfaEnrollment {
+            mfa_enrollment_id: "totp-factor".to_owned(),
+            display_name: None,
+            factor: FakeMfaFactor::Totp {
+                secret: "secret".to_owned(),
+            },
+        },
+    );
+    let login = || firebase.login("user@example.com".to_owned(), "password".to_owned());
+
+    let challenge = match login().await.unwrap_err().into_kind() {
+        LoginError::MfaRequired(challenge) => challenge,
+        other => panic!("expected an MFA challenge, got {:?}", other),
+    };
+    assert_eq!(challenge.mfa_info.len(), 2);
+    assert!(challenge.mfa_info[1].is_totp());
+    let serialized = serde_json::to_value(LoginError::MfaRequired(challenge.clone())).unwrap();
+    assert_eq!(
+        serialized["MfaRequired"]["mfaPendingCredential"],
+        challenge.mfa_pending_credential
+    );
+
+    let session_info = firebase
+        .mfa_sign_in_start(
+            challenge.mfa_pending_credential.clone(),
+            challenge.mfa_info[0].mfa_enrollment_id.clone(),
+            "recaptcha".to_owned(),
+        )
+        .await
+        .unwrap();
+    let signed_in = firebase
+        .mfa_sign_in_finalize(
+            challenge.mfa_pending_credential,
+            MfaVerification::Phone {
+                session_info,
+                code: server.phone_codes()[0].code.clone(),
+            },
+        )
+        .await
+        .unwrap();
+    let claims = firebase.verify_id_token(&signed_in.id_token).await.unwrap();
+    assert_eq!(claims.uid(), registered.local_id);
+
+    let challenge = match login().await.unwrap_err().into_kind() {
+        LoginError::MfaRequired(challenge) => challenge,
+        other => panic!("expected an MFA challenge, got {:?}", other),
+    };
+    let totp = |code: &str| MfaVerification::Totp {
+        mfa_enrollment_id: "totp-factor".to_owned(),
+        code: code.to_owned(),
+    };
+    assert!(matches!(
+        firebase
+            .mfa_sign_in_finalize(challenge.mfa_pending_credential.clone(), totp("000000"))
+            .await
+            .unwrap_err()
+            .kind(),
+        MfaError::InvalidCode
+    ));
+    firebase
+        .mfa_sign_in_finalize(challenge.mfa_pending_credential, totp(FAKE_TOTP_CODE))
+        .await
+        .unwrap();
+}
+
+#[actix_rt::test]
+async fn enrolls_and_withdraws_second_factors() {
+    let (server, firebase) = start();
+    let registered = firebase
+        .register("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+    assert!(matches!(
+        firebase
+            .mfa_enrollment_start(registered.id_token.clone(), MfaEnrollmentRequest::Totp)
+            .await
+            .unwrap_err()
+            .kind(),
+        MfaError::UnverifiedEmail
+    ));
+    // Without the emulator, the v2 API is reached at the configured URL.
+    let configured = Firebase::auth(
+        format!("http://{}/v1", server.host()),
+        "fake-api-key".to_owned(),
+        Arc::new(Client::default()),
+    )
+    .without_emulator()
+    .with_v2_base_url(format!(
+        "http://{}/identitytoolkit.googleapis.com/v2",
+        server.host()
+    ));
+    assert!(matches!(
+        configured
+            .mfa_enrollment_start(registered.id_token.clone(), MfaEnrollmentRequest::Totp)
+            .await
+            .unwrap_err()
+            .kind(),
+        MfaError::UnverifiedEmail
+    ));
+    firebase
+        .send_verification_email(registered.id_token.clone())
+        .await
+        .unwrap();
+    firebase
+        .apply_action_code(server.oob_codes()[0].oob_code.clone())
+        .await
+        .unwrap();
+
+    let secret = match firebase
+        .mfa_enrollment_start(registered.id_token.clone(), MfaEnrollmentRequest::Totp)
+        .await
+        .unwrap()
+    {
+        MfaEnrollmentSession::Totp(secret) => secret,
+        other => panic!("expected a TOTP secret, got {:?}", other),
+    };
+    assert_eq!(
+        secret.otpauth_uri("user@example.com", "My App"),
+        format!(
+            "otpauth://totp/My%20App:user%40example.com?secret={}&issuer=My%20App&algorithm=SHA1&digits=6&period=30",
+            secret.shared_secret_key
+        )
+    );
+    let enrolled = firebase
+        .mfa_enrollment_finalize(
+            registered.id_token,
+            MfaEnrollmentVerification::Totp {
+                session_info: secret.session_info,
+                code: FAKE_TOTP_CODE.to_owned(),
+            },
+            Some("Authenticator".to_owned()),
+        )
+        .await
+        .unwrap();
+
+    let session_info = match firebase
+        .mfa_enrollment_start(
+            enrolled.id_token.clone(),
+            MfaEnrollmentRequest::Phone {
+                phone_number: "+15555550100".to_owned(),
+                recaptcha_token: "recaptcha".to_owned(),
+            },
+        )
+        .await
+        .unwrap()
+    {
+        MfaEnrollmentSession::Phone { session_info } => session_info,
+        other => panic!("expected an SMS session, got {:?}", other),
+    };
+    let enrolled = firebase
+        .mfa_enrollment_finalize(
+            enrolled.id_token,
+            MfaEnrollmentVerification::Phone {
+                session_info,
+                code: server.phone_codes()[0].code.clone(),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    let user = firebase
+        .get_account_info(enrolled.id_token.clone())
+        .await
+        .unwrap();
+    assert_eq!(user.mfa_info.len(), 2);
+    assert!(user.mfa_info[0].is_totp());
+    assert_eq!(user.mfa_info[1].phone_info.as_deref(), Some("+15555550100"));
+    assert!(matches!(
+        firebase
+            .login("user@example.com".to_owned(), "password".to_owned())
+            .await
+            .unwrap_err()
+            .kind(),
+        LoginError::MfaRequired(_)
+    ));
+
+    let mut id_token = enrolled.id_token;
+    for enrollment in user.mfa_info {
+        id_token = firebase
+            .mfa_withdraw(id_token, enrollment.mfa_enrollment_id)
+            .await
+            .unwrap()
+            .id_token;
     }
-}
\ No newline at end of file
+    firebase
+        .login("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+}
+
+#[actix_rt::test]
+async fn preserves_error_details() {
+    let (_server, firebase) = start();
+
+    let error = firebase
+        .login("missing@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap_err();
+    assert!(matches!(error.kind(), LoginError::EmailNotFound));
+    assert_eq!(error.status(), Some(StatusCode::BAD_REQUEST));
+    let response = error.response().unwrap();
+    assert_eq!(response.message(), "EMAIL_NOT_FOUND");
+    assert_eq!(response.reasons().collect::<Vec<_>>(), ["invalid"]);
+    assert_eq!(error.to_string(), "Email not found: EMAIL_NOT_FOUND");
+    assert!(error.source().is_none());
+
+    let unreachable = Firebase::auth(
+        "http://127.0.0.1:1".to_owned(),
+        "key".to_owned(),
+        Arc::new(Client::default()),
+    )
+    .with_emulator("127.0.0.1:1".to_owned());
+    let error = unreachable.sign_in_anonymously().await.unwrap_err();
+    assert!(matches!(error.kind(), RegisterError::Unknown));
+    assert!(error.status().is_none());
+    assert!(error.send_error().is_some());
+    assert!(error.source().is_some());
+}
+
+#[actix_rt::test]
+async fn maps_error_codes_with_details() {
+    let (_server, firebase) = start();
+
+    let error = firebase
+        .register("user@example.com".to_owned(), "short".to_owned())
+        .await
+        .unwrap_err();
+    assert!(matches!(error.kind(), RegisterError::WeakPassword));
+    let response = error.response().unwrap();
+    assert_eq!(response.auth_code(), AuthErrorCode::WeakPassword);
+    assert_eq!(
+        response.detail(),
+        Some("Password should be at least 6 characters")
+    );
+
+    let error = firebase
+        .exchange_refresh_token("unknown".to_owned())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        RefreshTokenError::InvalidRefreshToken
+    ));
+
+    let expired: ErrorContainer = serde_json::from_value(json!({
+        "error": { "code": 400, "message": "TOKEN_EXPIRED", "status": "INVALID_ARGUMENT" }
+    }))
+    .unwrap();
+    assert!(matches!(
+        expired.error.refresh_token_error(),
+        RefreshTokenError::TokenExpired
+    ));
+
+    let error = firebase
+        .sign_in_with_custom_token(String::new())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.response().unwrap().auth_code(),
+        AuthErrorCode::MissingCustomToken
+    );
+    assert!(matches!(error.kind(), CustomTokenError::InvalidCustomToken));
+
+    let error = |message: &str| {
+        serde_json::from_value::<ErrorContainer>(json!({
+            "error": { "code": 400, "message": message, "status": "INVALID_ARGUMENT" }
+        }))
+        .unwrap()
+        .error
+    };
+    assert!(matches!(
+        error("RESET_PASSWORD_EXCEED_LIMIT").password_reset_error(),
+        PasswordResetError::TooManyAttempts
+    ));
+    assert!(matches!(
+        error("EMAIL_CHANGE_NEEDS_VERIFICATION").update_credential_error(),
+        UpdateCredentialError::EmailChangeNeedsVerification
+    ));
+    assert!(matches!(
+        error("INVALID_APP_CREDENTIAL").phone_auth_error(),
+        PhoneAuthError::CaptchaCheckFailed
+    ));
+    assert!(matches!(
+        error("UNAUTHENTICATED").user_management_error(),
+        UserManagementError::InsufficientPermission
+    ));
+    assert_eq!(
+        AuthErrorCode::parse("SOMETHING_NEW : Details"),
+        (
+            AuthErrorCode::Other("SOMETHING_NEW".to_owned()),
+            Some("Details")
+        )
+    );
+}
+
+#[actix_rt::test]
+async fn session_refreshes_id_token() {
+    let (server, firebase) = start();
+
+    let registered = firebase
+        .register("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+    let local_id = registered.local_id.clone();
+    let id_token = registered.id_token.clone();
+    let refresh_token = registered.refresh_token.clone();
+
+    let session = firebase.session(registered);
+    assert_eq!(session.get_valid_id_token().await.unwrap(), id_token);
+    assert_eq!(server.token_exchanges(), 0);
+
+    // Every token is within a two hour skew of its expiry.
+    let session = session.with_refresh_skew(Duration::from_secs(2 * 60 * 60));
+    let (first, second) = join(session.get_valid_id_token(), session.get_valid_id_token()).await;
+    assert_eq!(first.unwrap(), second.unwrap());
+    assert_eq!(server.token_exchanges(), 1);
+    assert_ne!(session.tokens().refresh_token, refresh_token);
+
+    server.set_disabled(&local_id, true);
+    let error = session.get_valid_id_token().await.unwrap_err();
+    assert!(matches!(error.kind(), RefreshTokenError::UserDisabled));
+}
+
+#[actix_rt::test]
+async fn session_persists_tokens() {
+    let (_server, firebase) = start();
+    let registered = firebase
+        .register("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+
+    let path = env::temp_dir().join(format!("session-{}.json", std::process::id()));
+    let store = Rc::new(JsonFileSessionStore::new(&path));
+    let rotated = Rc::new(Cell::new(0));
+    let session = firebase
+        .session(registered)
+        .with_store(store.clone())
+        .on_refresh_token_rotated({
+            let rotated = rotated.clone();
+            move |_| rotated.set(rotated.get() + 1)
+        });
+    session.save().await.unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    let id_token = session.refresh().await.unwrap();
+    assert_eq!(rotated.get(), 1);
+
+    let restored = Session::restore(firebase.clone(), store.clone())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(restored.get_valid_id_token().await.unwrap(), id_token);
+    assert_eq!(
+        restored.tokens().refresh_token,
+        session.tokens().refresh_token
+    );
+
+    store.clear().await.unwrap();
+    assert!(Session::restore(firebase.clone(), store)
+        .await
+        .unwrap()
+        .is_none());
+
+    let memory = Rc::new(MemorySessionStore::new());
+    assert!(Session::restore(firebase.clone(), memory)
+        .await
+        .unwrap()
+        .is_none());
+
+    let missing_dir = env::temp_dir().join(format!("missing-{}", std::process::id()));
+    let save_errors = Rc::new(Cell::new(0));
+    let session = session
+        .with_store(Rc::new(JsonFileSessionStore::new(
+            missing_dir.join("session.json"),
+        )))
+        .on_save_error({
+            let save_errors = save_errors.clone();
+            move |_| save_errors.set(save_errors.get() + 1)
+        });
+    let id_token = session.refresh().await.unwrap();
+    assert_eq!(save_errors.get(), 1);
+    assert_eq!(session.tokens().id_token, id_token);
+}
+
+#[actix_rt::test]
+async fn creates_and_verifies_session_cookies() {
+    let (server, firebase) = start();
+    let registered = firebase
+        .register("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+
+    let cookie = firebase
+        .create_session_cookie(registered.id_token.clone(), Duration::from_secs(60 * 60))
+        .await
+        .unwrap();
+    let claims = firebase.verify_session_cookie(&cookie).await.unwrap();
+    assert_eq!(claims.uid(), registered.local_id);
+    assert!(matches!(
+        firebase.verify_session_cookie(&registered.id_token).await,
+        Err(VerifyError::InvalidIssuer)
+    ));
+    assert!(matches!(
+        firebase.verify_id_token(&cookie).await,
+        Err(VerifyError::InvalidIssuer)
+    ));
+
+    let error = firebase
+        .create_session_cookie(registered.id_token.clone(), Duration::from_secs(60))
+        .await
+        .unwrap_err();
+    assert!(matches!(error.kind(), SessionCookieError::InvalidDuration));
+    assert!(error.status().is_none());
+    let error = firebase
+        .create_session_cookie("invalid".to_owned(), Duration::from_secs(60 * 60))
+        .await
+        .unwrap_err();
+    assert!(matches!(error.kind(), SessionCookieError::InvalidIdToken));
+
+    // Outside of the emulator, admin requests need a service account's access token.
+    let production = Firebase::auth(
+        format!("http://{}/identitytoolkit.googleapis.com/v1", server.host()),
+        "fake-api-key".to_owned(),
+        Arc::new(Client::default()),
+    )
+    .without_emulator()
+    .with_project_id(FAKE_PROJECT_ID.to_owned());
+    let error = production
+        .create_session_cookie(registered.id_token.clone(), Duration::from_secs(60 * 60))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        SessionCookieError::MissingCredentials
+    ));
+    assert_eq!(error.status(), None);
+
+    let mut service_account =
+        ServiceAccount::from_file("tests/fixtures/service_account.json").unwrap();
+    service_account.token_uri = server.token_uri();
+    let production = production.with_service_account(service_account);
+    production
+        .create_session_cookie(registered.id_token, Duration::from_secs(60 * 60))
+        .await
+        .unwrap();
+}
+
+#[actix_rt::test]
+async fn revokes_refresh_tokens() {
+    let (server, firebase) = start();
+    let registered = firebase
+        .register("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+    let cookie = firebase
+        .create_session_cookie(registered.id_token.clone(), Duration::from_secs(60 * 60))
+        .await
+        .unwrap();
+    let check_revoked = VerifyOptions {
+        check_revoked: true,
+    };
+    firebase
+        .verify_id_token_with(&registered.id_token, &check_revoked)
+        .await
+        .unwrap();
+
+    let revoked_at = UNIX_EPOCH.elapsed().unwrap().as_secs();
+    firebase
+        .revoke_refresh_tokens(&registered.local_id)
+        .await
+        .unwrap();
+    let user = firebase.get_user(&registered.local_id).await.unwrap();
+    assert!(user.valid_since.is_some());
+    // Revocation has a granularity of one second, so it is moved past the tokens issued so far.
+    server.set_valid_since(&registered.local_id, revoked_at + 1);
+
+    firebase
+        .verify_id_token(&registered.id_token)
+        .await
+        .unwrap();
+    assert!(matches!(
+        firebase
+            .verify_id_token_with(&registered.id_token, &check_revoked)
+            .await,
+        Err(VerifyError::Revoked)
+    ));
+    assert!(matches!(
+        firebase
+            .verify_session_cookie_with(&cookie, &check_revoked)
+            .await,
+        Err(VerifyError::Revoked)
+    ));
+    let error = firebase
+        .exchange_refresh_token(registered.refresh_token)
+        .await
+        .unwrap_err();
+    assert!(matches!(error.kind(), RefreshTokenError::TokenExpired));
+
+    server.set_valid_since(&registered.local_id, revoked_at);
+    let login = firebase
+        .login("user@example.com".to_owned(), "password".to_owned())
+        .await
+        .unwrap();
+    firebase
+        .verify_id_token_with(&login.id_token, &check_revoked)
+        .await
+        .unwrap();
+
+    server.set_disabled(&login.local_id, true);
+    assert!(matches!(
+        firebase
+            .verify_id_token_with(&login.id_token, &check_revoked)
+            .await,
+        Err(VerifyError::UserDisabled)
+    ));
+
+    let error = firebase.get_user("unknown").await.unwrap_err();
+    assert!(matches!(error.kind(), UserManagementError::UserNotFound));
+    let error = firebase.revoke_refresh_tokens("").await.unwrap_err();
+    assert!(matches!(error.kind(), UserManagementError::InvalidUid));
+
+    server.set_disabled(&login.local_id, false);
+    firebase
+        .delete_account(login.id_token.clone())
+        .await
+        .unwrap();
+    assert!(matches!(
+        firebase
+            .verify_id_token_with(&login.id_token, &check_revoked)
+            .await,
+        Err(VerifyError::UserNotFound)
+    ));
+    let production = Firebase::auth(
+        format!("http://{}/identitytoolkit.googleapis.com/v1", server.host()),
+        "fake-api-key".to_owned(),
+        Arc::new(Client::default()),
+    )
+    .without_emulator()
+    .with_project_id(FAKE_PROJECT_ID.to_owned());
+    let error = production
+        .revoke_refresh_tokens(&login.local_id)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        UserManagementError::MissingCredentials
+    ));
+    assert_eq!(error.status(), None);
+}
@@ -0,0 +1,135 @@
+#![allow(clippy::arc_with_non_send_sync)]
+
+mod common;
+
+use std::{error::Error, rc::Rc, sync::Arc, time::Duration};
+
+use awc::Client;
+use awc_firebase_auth::{
+    verify::{VerifyError, VerifyOptions},
+    Firebase,
+};
+use common::{firebase, keys, now, sign, valid_claims, KEY_ID, OTHER_KEY, PROJECT_ID, SIGNING_KEY};
+use serde_json::json;
+
+#[actix_rt::test]
+async fn verifies_valid_token() {
+    let token = sign(&valid_claims(), SIGNING_KEY, KEY_ID);
+
+    let claims = firebase().verify_id_token(&token).await.unwrap();
+
+    assert_eq!(claims.uid(), "user-1");
+    assert_eq!(claims.email.as_deref(), Some("user@example.com"));
+    assert_eq!(claims.firebase.sign_in_provider, "password");
+    assert_eq!(claims.custom_claims.get("role"), Some(&json!("admin")));
+}
+
+#[actix_rt::test]
+async fn rejects_invalid_tokens() {
+    let key = SIGNING_KEY;
+    let firebase = firebase();
+
+    let mut expired = valid_claims();
+    expired["exp"] = json!(now() - 600);
+    let mut wrong_audience = valid_claims();
+    wrong_audience["aud"] = json!("other-project");
+    let mut wrong_issuer = valid_claims();
+    wrong_issuer["iss"] = json!("https://securetoken.google.com/other-project");
+    let mut future_auth_time = valid_claims();
+    future_auth_time["auth_time"] = json!(now() + 600);
+    let mut empty_subject = valid_claims();
+    empty_subject["sub"] = json!("");
+
+    let cases = [
+        (sign(&expired, key, KEY_ID), "Token expired"),
+        (sign(&wrong_audience, key, KEY_ID), "Invalid audience"),
+        (sign(&wrong_issuer, key, KEY_ID), "Invalid issuer"),
+        (sign(&future_auth_time, key, KEY_ID), "Invalid auth time"),
+        (sign(&empty_subject, key, KEY_ID), "Invalid subject"),
+        (
+            sign(&valid_claims(), OTHER_KEY, KEY_ID),
+            "Invalid signature",
+        ),
+        (
+            sign(&valid_claims(), key, "unknown"),
+            "Unknown key id: unknown",
+        ),
+        ("not-a-token".to_owned(), "Malformed token"),
+    ];
+
+    for (token, expected) in cases {
+        let err = firebase.verify_id_token(&token).await.unwrap_err();
+        assert_eq!(err.to_string(), expected);
+    }
+}
+
+#[actix_rt::test]
+async fn tolerates_clock_skew() {
+    let mut claims = valid_claims();
+    claims["iat"] = json!(now() + 60);
+    claims["exp"] = json!(now() - 60);
+    let token = sign(&claims, SIGNING_KEY, KEY_ID);
+
+    assert!(firebase().verify_id_token(&token).await.is_ok());
+    assert!(matches!(
+        firebase()
+            .with_clock_skew(Duration::ZERO)
+            .verify_id_token(&token)
+            .await,
+        Err(VerifyError::Expired)
+    ));
+}
+
+#[actix_rt::test]
+async fn errors_expose_their_source() {
+    let token = sign(&valid_claims(), SIGNING_KEY, KEY_ID);
+    let check_revoked = VerifyOptions {
+        check_revoked: true,
+    };
+
+    // Without admin credentials the user can't be looked up.
+    let err: Box<dyn Error> = firebase()
+        .verify_id_token_with(&token, &check_revoked)
+        .await
+        .unwrap_err()
+        .into();
+    assert_eq!(
+        err.source().unwrap().to_string(),
+        "Missing admin credentials"
+    );
+}
+
+#[actix_rt::test]
+async fn requires_project_id() {
+    let token = sign(&valid_claims(), SIGNING_KEY, KEY_ID);
+    let firebase = Firebase::auth(
+        "http://localhost".to_owned(),
+        "api-key".to_owned(),
+        Arc::new(Client::default()),
+    );
+
+    assert!(matches!(
+        firebase.verify_id_token(&token).await,
+        Err(VerifyError::MissingProjectId)
+    ));
+}
+
+#[actix_rt::test]
+async fn verifies_session_cookie() {
+    let mut claims = valid_claims();
+    claims["iss"] = json!(format!(
+        "https://session.firebase.google.com/{}",
+        PROJECT_ID
+    ));
+    let cookie = sign(&claims, SIGNING_KEY, KEY_ID);
+
+    let firebase = firebase().with_session_cookie_key_source(Rc::new(keys()));
+    let verified = firebase.verify_session_cookie(&cookie).await.unwrap();
+    assert_eq!(verified.uid(), "user-1");
+
+    let id_token = sign(&valid_claims(), SIGNING_KEY, KEY_ID);
+    assert!(matches!(
+        firebase.verify_session_cookie(&id_token).await,
+        Err(VerifyError::InvalidIssuer)
+    ));
+}